@@ -8,6 +8,7 @@ use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::str;
 
+use cargo_cargofmt::config::ConfigOverrides;
 use cargo_metadata::Edition;
 use cargo_metadata::Metadata;
 use cargo_metadata::Package;
@@ -46,6 +47,22 @@ struct Opts {
     /// Run rustfmt in check mode
     #[arg(long)]
     check: bool,
+
+    /// Report which config file applies to each package
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Use the config file at this path instead of searching for one
+    #[arg(long, value_name = "PATH")]
+    config_path: Option<PathBuf>,
+
+    /// Override a config value (can be repeated)
+    #[arg(
+        long = "config",
+        value_name = "KEY=VALUE",
+        value_parser = ConfigOverrides::parse_value
+    )]
+    config: Vec<(String, String)>,
 }
 
 fn main() {
@@ -62,15 +79,31 @@ fn execute() -> i32 {
     let CargoOpts::Cargofmt(opts) = opts;
 
     let strategy = CargoFmtStrategy::from_opts(&opts);
+    let overrides = ConfigOverrides {
+        config_path: opts.config_path.clone(),
+        values: opts.config.clone(),
+    };
 
     if let Some(manifest_path) = opts.manifest_path.clone() {
         if manifest_path.file_name() != Some(std::ffi::OsStr::new("Cargo.toml")) {
             print_usage_to_stderr("the manifest-path must be a path to a Cargo.toml file");
             return FAILURE;
         }
-        handle_command_status(format_crates(&strategy, opts.check, Some(&manifest_path)))
+        handle_command_status(format_crates(
+            &strategy,
+            &overrides,
+            opts.check,
+            opts.verbose,
+            Some(&manifest_path),
+        ))
     } else {
-        handle_command_status(format_crates(&strategy, opts.check, None))
+        handle_command_status(format_crates(
+            &strategy,
+            &overrides,
+            opts.check,
+            opts.verbose,
+            None,
+        ))
     }
 }
 
@@ -226,16 +259,25 @@ impl Hash for Target {
 #[tracing::instrument]
 fn format_crates(
     strategy: &CargoFmtStrategy,
+    overrides: &ConfigOverrides,
     check: bool,
+    verbose: bool,
     manifest_path: Option<&Path>,
 ) -> Result<i32, io::Error> {
     let metadata = get_cargo_metadata(manifest_path)?;
     let packages = get_packages(strategy, manifest_path, &metadata)?;
     let _targets = to_targets(&packages);
+    let workspace_manifest = metadata.workspace_root.join("Cargo.toml");
 
     let mut errors = 0;
     for package in packages.values() {
-        if let Err(err) = format_crate(check, package) {
+        if let Err(err) = format_crate(
+            overrides,
+            check,
+            verbose,
+            package,
+            workspace_manifest.as_std_path(),
+        ) {
             if let Some(err) = err {
                 anstream::eprintln!("{err}");
             }
@@ -278,8 +320,42 @@ fn rustfmt(strategy: &CargoFmtStrategy, check: bool, manifest_path: Option<&Path
 }
 
 #[tracing::instrument]
-fn format_crate(check: bool, package: &Package) -> Result<(), Option<io::Error>> {
-    let config = cargo_cargofmt::config::load_config(package.manifest_path.as_std_path())?;
+fn format_crate(
+    overrides: &ConfigOverrides,
+    check: bool,
+    verbose: bool,
+    package: &Package,
+    workspace_manifest: &Path,
+) -> Result<(), Option<io::Error>> {
+    let mut config_warnings = Vec::new();
+    let config = cargo_cargofmt::config::load_config_with_overrides(
+        package.manifest_path.as_std_path(),
+        overrides,
+        &mut config_warnings,
+    )?;
+    for warning in config_warnings {
+        anstream::eprintln!("warning: {warning}");
+    }
+    if verbose {
+        match &config.source {
+            Some(source) => anstream::eprintln!(
+                "Using config file `{}` for `{}`",
+                source.display(),
+                package.manifest_path
+            ),
+            None => anstream::eprintln!("Using default config for `{}`", package.manifest_path),
+        }
+    }
+
+    // Also for manifests the config leaves alone, so a mismatch never goes unnoticed
+    config.check_required_version()?;
+
+    if config.is_ignored(package.manifest_path.as_std_path()) {
+        if verbose {
+            anstream::eprintln!("Skipping ignored `{}`", package.manifest_path);
+        }
+        return Ok(());
+    }
 
     if config.disable_all_formatting {
         return Ok(());
@@ -289,7 +365,31 @@ fn format_crate(check: bool, package: &Package) -> Result<(), Option<io::Error>>
         .map_err(io::Error::other)
         .map_err(Some)?;
 
-    let Some(formatted) = cargo_cargofmt::fmt_manifest(&raw_input_text, config) else {
+    let workspace_manifest = if config.inherit_workspace_fields {
+        let workspace_manifest = cargo_util::paths::read(workspace_manifest)
+            .map_err(io::Error::other)
+            .map_err(Some)?;
+        Some(workspace_manifest)
+    } else {
+        None
+    };
+
+    let mut warnings = Vec::new();
+    let mut changes = Vec::new();
+    let formatted = cargo_cargofmt::fmt_manifest_with_warnings(
+        &raw_input_text,
+        workspace_manifest.as_deref(),
+        config,
+        &mut warnings,
+        &mut changes,
+    )?;
+    for change in changes {
+        anstream::eprintln!("note: {}: {change}", package.manifest_path);
+    }
+    for warning in warnings {
+        anstream::eprintln!("warning: {}: {warning}", package.manifest_path);
+    }
+    let Some(formatted) = formatted else {
         return Ok(());
     };
 
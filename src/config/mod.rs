@@ -2,13 +2,21 @@ use std::io;
 use std::path::Path;
 use std::path::PathBuf;
 
+use serde::Deserialize as _;
+
 pub mod lists;
 pub mod options;
 
-#[derive(serde::Deserialize)]
+#[derive(Debug, serde::Deserialize)]
 #[serde(default)]
 pub struct Config {
     pub disable_all_formatting: bool,
+    #[serde(alias = "version")]
+    pub style_edition: options::StyleEdition,
+    /// Versions of cargo-cargofmt allowed to format with this config, like `">=0.2, <0.3"`
+    ///
+    /// Named apart from rustfmt's `required_version`, which shares the config file.
+    pub cargofmt_required_version: Option<semver::VersionReq>,
     pub newline_style: options::NewlineStyle,
     pub format_generated_files: bool,
     pub generated_marker_line_search_limit: usize,
@@ -20,6 +28,56 @@ pub struct Config {
     pub max_width: usize,
     pub array_width: Option<usize>,
     pub use_small_heuristics: options::UseSmallHeuristics,
+    pub normalize_strings: bool,
+    pub normalize_datetime_separators: Option<bool>,
+    pub remove_unused_parent_tables: Option<bool>,
+    pub reflow_arrays: Option<bool>,
+    pub dotted_keys: options::DottedKeys,
+    pub rewrite_deprecated_keys: bool,
+    pub normalize_target_cfgs: bool,
+    /// Sort the operands of `any` and `all`, for `normalize_target_cfgs`
+    pub sort_cfg_predicates: bool,
+    pub sort_dependencies: bool,
+    pub reorder_tables: bool,
+    /// Order of tables by their top-level key, for `reorder_tables`
+    pub table_order: Vec<String>,
+    pub group_subtables: bool,
+    pub convert_dependency_tables: bool,
+    pub inline_table_width: Option<usize>,
+    pub collapse_version_only_dependencies: bool,
+    pub reorder_dependency_keys: bool,
+    /// Order of keys in dependency specifications, for `reorder_dependency_keys`
+    pub dependency_key_order: Vec<String>,
+    pub normalize_features: bool,
+    /// Sort features other than `default` by name, for `normalize_features`
+    pub sort_feature_keys: bool,
+    pub sort_lints: bool,
+    pub normalize_version_reqs: bool,
+    pub normalize_paths: bool,
+    pub normalize_licenses: bool,
+    pub remove_redundant_defaults: bool,
+    /// Needs the workspace root's manifest, which `cargo cargofmt` loads
+    pub inherit_workspace_fields: bool,
+    pub sort_string_arrays: bool,
+    /// Dotted keys of arrays to sort, for `sort_string_arrays`
+    pub sorted_arrays: Vec<String>,
+    pub reorder_package_keys: bool,
+    /// Order of keys in `[package]`, for `reorder_package_keys`
+    ///
+    /// `*` stands for all keys not otherwise listed.
+    pub package_key_order: Vec<String>,
+    /// Manifests to not format, as globs relative to the config file
+    #[serde(deserialize_with = "deserialize_globs")]
+    pub ignore: Vec<glob::Pattern>,
+    /// Tables to leave untouched, like `package.metadata.release` or `workspace.metadata.*`
+    ///
+    /// Each pattern also covers the subtables of the tables it matches, and keys reaching
+    /// into them from other tables.  Settings that add, remove or rename tables, like
+    /// `convert_dependency_tables`, leave matching tables alone too.
+    pub skip_tables: Vec<String>,
+    /// The config file these settings were read from, if any
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
 }
 
 impl Config {
@@ -35,6 +93,85 @@ impl Config {
             .unwrap_or_else(|| self.heuristic_width(0.6))
     }
 
+    /// Fails if the running cargo-cargofmt does not satisfy `cargofmt_required_version`
+    pub fn check_required_version(&self) -> Result<(), io::Error> {
+        let Some(required) = &self.cargofmt_required_version else {
+            return Ok(());
+        };
+        let installed = installed_version();
+        if required.matches(&installed) {
+            return Ok(());
+        }
+
+        let source = self
+            .source
+            .as_deref()
+            .map(|p| format!("`{}`", p.display()))
+            .unwrap_or_else(|| "config".to_owned());
+        Err(io::Error::other(format!(
+            "{source} requires cargo-cargofmt `{required}` but {installed} is installed"
+        )))
+    }
+
+    /// Whether `path` is excluded from formatting by `ignore`
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let Some(source) = self.source.as_deref() else {
+            return false;
+        };
+        // Relative paths have no parent to match `ignore` against
+        let (Ok(source), Ok(path)) = (std::path::absolute(source), std::path::absolute(path))
+        else {
+            return false;
+        };
+        let Some(relative) = source
+            .parent()
+            .and_then(|base| path.strip_prefix(base).ok())
+        else {
+            return false;
+        };
+        matches_path(&self.ignore, relative)
+    }
+
+    /// Returns the widest a dependency may be as an inline table.
+    ///
+    /// If `inline_table_width` is explicitly set, returns that value.
+    /// Otherwise, calculates based on `use_small_heuristics`:
+    /// - `Default`: `max_width`
+    /// - `Off`: 0 (always a subtable)
+    /// - `Max`: `max_width`
+    pub fn inline_table_width(&self) -> usize {
+        self.inline_table_width
+            .unwrap_or_else(|| self.heuristic_width(1.0))
+    }
+
+    /// Returns whether to normalize date-time separators.
+    ///
+    /// Unless explicitly set, enabled starting with `style_edition = "2024"`.
+    pub fn normalize_datetime_separators(&self) -> bool {
+        self.normalize_datetime_separators
+            .unwrap_or_else(|| self.is_style_edition(options::StyleEdition::Edition2024))
+    }
+
+    /// Returns whether to remove empty parent tables.
+    ///
+    /// Unless explicitly set, enabled starting with `style_edition = "2024"`.
+    pub fn remove_unused_parent_tables(&self) -> bool {
+        self.remove_unused_parent_tables
+            .unwrap_or_else(|| self.is_style_edition(options::StyleEdition::Edition2024))
+    }
+
+    /// Returns whether to reflow arrays according to [`Config::array_width`].
+    ///
+    /// Unless explicitly set, enabled starting with `style_edition = "2024"`.
+    pub fn reflow_arrays(&self) -> bool {
+        self.reflow_arrays
+            .unwrap_or_else(|| self.is_style_edition(options::StyleEdition::Edition2024))
+    }
+
+    fn is_style_edition(&self, at_least: options::StyleEdition) -> bool {
+        at_least <= self.style_edition
+    }
+
     fn heuristic_width(&self, percent: f64) -> usize {
         match self.use_small_heuristics {
             options::UseSmallHeuristics::Default => (self.max_width as f64 * percent) as usize,
@@ -48,6 +185,8 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             disable_all_formatting: false,
+            style_edition: options::StyleEdition::default(),
+            cargofmt_required_version: None,
             newline_style: options::NewlineStyle::default(),
             format_generated_files: false,
             generated_marker_line_search_limit: 5,
@@ -59,40 +198,373 @@ impl Default for Config {
             max_width: 100,
             array_width: None,
             use_small_heuristics: options::UseSmallHeuristics::default(),
+            normalize_strings: true,
+            normalize_datetime_separators: None,
+            remove_unused_parent_tables: None,
+            reflow_arrays: None,
+            dotted_keys: options::DottedKeys::default(),
+            rewrite_deprecated_keys: false,
+            normalize_target_cfgs: false,
+            sort_cfg_predicates: false,
+            sort_dependencies: false,
+            reorder_tables: false,
+            table_order: [
+                "package",
+                "lib",
+                "bin",
+                "example",
+                "test",
+                "bench",
+                "features",
+                "dependencies",
+                "dev-dependencies",
+                "build-dependencies",
+                "target",
+                "lints",
+                "profile",
+                "workspace",
+            ]
+            .map(String::from)
+            .to_vec(),
+            group_subtables: false,
+            convert_dependency_tables: false,
+            inline_table_width: None,
+            collapse_version_only_dependencies: false,
+            reorder_dependency_keys: false,
+            dependency_key_order: [
+                "workspace",
+                "version",
+                "path",
+                "git",
+                "branch",
+                "tag",
+                "rev",
+                "registry",
+                "package",
+                "default-features",
+                "features",
+                "optional",
+                "public",
+            ]
+            .map(String::from)
+            .to_vec(),
+            normalize_features: false,
+            sort_feature_keys: false,
+            sort_lints: false,
+            normalize_version_reqs: false,
+            normalize_paths: false,
+            normalize_licenses: false,
+            remove_redundant_defaults: false,
+            inherit_workspace_fields: false,
+            sort_string_arrays: false,
+            sorted_arrays: [
+                "workspace.members",
+                "workspace.exclude",
+                "workspace.default-members",
+                "package.include",
+                "package.exclude",
+                "package.keywords",
+                "package.categories",
+            ]
+            .map(String::from)
+            .to_vec(),
+            reorder_package_keys: false,
+            package_key_order: ["name", "version", "*", "description"]
+                .map(String::from)
+                .to_vec(),
+            ignore: Vec::new(),
+            skip_tables: Vec::new(),
+            source: None,
         }
     }
 }
 
+/// Settings layered over the config file, usually from the command-line
+#[derive(Clone, Default, Debug)]
+pub struct ConfigOverrides {
+    /// Read this config file instead of searching for one
+    pub config_path: Option<PathBuf>,
+    /// `key=value` pairs, applied in order
+    pub values: Vec<(String, String)>,
+}
+
+impl ConfigOverrides {
+    /// Parse a `key=value` pair as passed to `--config`
+    pub fn parse_value(arg: &str) -> Result<(String, String), String> {
+        let Some((key, value)) = arg.split_once('=') else {
+            return Err(format!("expected `KEY=VALUE`, found `{arg}`"));
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(format!("missing key in `{arg}`"));
+        }
+        Ok((key.to_owned(), value.trim().to_owned()))
+    }
+}
+
 #[tracing::instrument]
 pub fn load_config(search_start: &Path) -> Result<Config, io::Error> {
-    let Some(path) = find_config(search_start) else {
-        return Ok(Config::default());
+    load_config_with_overrides(search_start, &ConfigOverrides::default(), &mut Vec::new())
+}
+
+/// Loads the config for `search_start` with `overrides` layered over it
+///
+/// Unknown keys, like options meant for rustfmt, are skipped and reported in `warnings`.
+#[tracing::instrument(skip(warnings))]
+pub fn load_config_with_overrides(
+    search_start: &Path,
+    overrides: &ConfigOverrides,
+    warnings: &mut Vec<String>,
+) -> Result<Config, io::Error> {
+    // Relative paths have no parent to resolve `[[overrides]]` against
+    let search_start = &std::path::absolute(search_start)?;
+    let path = overrides
+        .config_path
+        .clone()
+        .or_else(|| find_config(search_start))
+        .map(std::path::absolute)
+        .transpose()?;
+
+    let mut table = match &path {
+        Some(path) => {
+            let mut table = read_config_table(path, warnings)?;
+            apply_path_overrides(&mut table, path, search_start, warnings)?;
+            table
+        }
+        None => toml::Table::new(),
     };
+    apply_overrides(&mut table, &overrides.values, warnings)?;
 
-    let content = std::fs::read_to_string(&path)?;
-    let config = toml::de::from_str(&content).map_err(io::Error::other)?;
+    let mut config = Config::deserialize(table).map_err(io::Error::other)?;
+    config.source = path;
     Ok(config)
 }
 
-fn find_config(mut path: &Path) -> Option<PathBuf> {
+fn read_config_table(path: &Path, warnings: &mut Vec<String>) -> Result<toml::Table, io::Error> {
+    let content = std::fs::read_to_string(path)?;
+    // Validate against `Config` first so errors point into the file
+    let _: Config = toml::de::from_str(&content).map_err(io::Error::other)?;
+    let table: toml::Table = toml::de::from_str(&content).map_err(io::Error::other)?;
+
+    let mut layer = table.clone();
+    layer.remove(PATH_OVERRIDES_KEY);
+    validate_layer(&layer, &path.display().to_string(), warnings)?;
+    Ok(table)
+}
+
+fn apply_overrides(
+    table: &mut toml::Table,
+    values: &[(String, String)],
+    warnings: &mut Vec<String>,
+) -> Result<(), io::Error> {
+    for (key, raw) in values {
+        let context = format!("--config {key}={raw}");
+        let layer = |value| toml::Table::from_iter([(key.clone(), value)]);
+        let string = || toml::Value::String(raw.clone());
+        // Allow unquoted strings, like `newline_style=Unix`
+        let value = raw.parse::<toml::Value>().ok();
+        let mut parsed = layer(value.clone().unwrap_or_else(string));
+        if let Err(err) = validate_layer(&parsed, &context, warnings) {
+            // Strings may also look like other values, like `style_edition=2021`
+            if value.map(|v| v.is_str()).unwrap_or(true) {
+                return Err(err);
+            }
+            parsed = layer(string());
+            validate_layer(&parsed, &context, warnings).map_err(|_| err)?;
+        }
+
+        table.extend(parsed);
+    }
+    Ok(())
+}
+
+/// Merges each `[[overrides]]` entry whose `paths` match `search_start` into `table`
+///
+/// Paths are relative to the directory of the config file; a pattern matches
+/// when it matches the manifest or one of the directories containing it.
+/// Later entries take precedence over earlier ones.
+fn apply_path_overrides(
+    table: &mut toml::Table,
+    config_path: &Path,
+    search_start: &Path,
+    warnings: &mut Vec<String>,
+) -> Result<(), io::Error> {
+    let Some(entries) = table.remove(PATH_OVERRIDES_KEY) else {
+        return Ok(());
+    };
+    let toml::Value::Array(entries) = entries else {
+        return Err(invalid_input(format!(
+            "`{PATH_OVERRIDES_KEY}` must be an array of tables"
+        )));
+    };
+    let relative = config_path
+        .parent()
+        .and_then(|base| search_start.strip_prefix(base).ok());
+
+    for (i, entry) in entries.into_iter().enumerate() {
+        let context = format!("{PATH_OVERRIDES_KEY}[{i}]");
+        let toml::Value::Table(mut entry) = entry else {
+            return Err(invalid_input(format!("`{context}` must be a table")));
+        };
+        let patterns = match entry.remove("paths") {
+            Some(paths) => {
+                let context = format!("{context}.paths");
+                let paths = Vec::<String>::deserialize(paths).map_err(|_| {
+                    invalid_input(format!("`{context}` must be an array of strings"))
+                })?;
+                compile_globs(&paths, &context)?
+            }
+            None => return Err(invalid_input(format!("`{context}` is missing `paths`"))),
+        };
+        validate_layer(&entry, &context, warnings)?;
+
+        if relative
+            .map(|relative| matches_path(&patterns, relative))
+            .unwrap_or(false)
+        {
+            table.extend(entry);
+        }
+    }
+    Ok(())
+}
+
+fn compile_globs(patterns: &[String], context: &str) -> Result<Vec<glob::Pattern>, io::Error> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            glob::Pattern::new(pattern).map_err(|err| {
+                invalid_input(format!("invalid glob `{pattern}` in `{context}`: {err}"))
+            })
+        })
+        .collect()
+}
+
+/// Compiles globs when reading the config, so invalid ones are reported there
+fn deserialize_globs<'de, D>(deserializer: D) -> Result<Vec<glob::Pattern>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|pattern| {
+            glob::Pattern::new(pattern)
+                .map_err(|err| serde::de::Error::custom(format!("invalid glob `{pattern}`: {err}")))
+        })
+        .collect()
+}
+
+/// Whether any pattern matches `relative` or one of its parent directories
+fn matches_path(patterns: &[glob::Pattern], relative: &Path) -> bool {
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    relative
+        .ancestors()
+        .filter(|p| !p.as_os_str().is_empty())
+        .any(|p| patterns.iter().any(|g| g.matches_path_with(p, options)))
+}
+
+/// Checks that the values in `layer` are valid, reporting unknown keys in `warnings`
+fn validate_layer(
+    layer: &toml::Table,
+    context: &str,
+    warnings: &mut Vec<String>,
+) -> Result<(), io::Error> {
+    let mut unknown = Vec::new();
+    let _: Config = serde_ignored::deserialize(layer.clone(), |path| {
+        unknown.push(path.to_string());
+    })
+    .map_err(|err| io::Error::other(format!("invalid `{context}`: {err}")))?;
+    warnings.extend(
+        unknown
+            .into_iter()
+            .map(|unknown| format!("unknown configuration option `{unknown}` in `{context}`")),
+    );
+    Ok(())
+}
+
+fn installed_version() -> semver::Version {
+    semver::Version::parse(env!("CARGO_PKG_VERSION")).expect("cargo validates package versions")
+}
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+const PATH_OVERRIDES_KEY: &str = "overrides";
+
+/// Names a config file to use in place of the user config
+pub const CONFIG_PATH_ENV: &str = "CARGOFMT_CONFIG";
+
+/// Looks for the config file that applies to `search_start`
+///
+/// In order of precedence:
+/// - the nearest config file in `search_start` or its ancestors
+/// - the file named by [`CONFIG_PATH_ENV`]
+/// - the user config in `$XDG_CONFIG_HOME/cargofmt/` or `$HOME/.config/cargofmt/`
+fn find_config(search_start: &Path) -> Option<PathBuf> {
+    find_config_with_env(
+        search_start,
+        std::env::var_os(CONFIG_PATH_ENV),
+        std::env::var_os("XDG_CONFIG_HOME"),
+        std::env::var_os("HOME"),
+    )
+}
+
+fn find_config_with_env(
+    search_start: &Path,
+    config_path: Option<std::ffi::OsString>,
+    xdg_config_home: Option<std::ffi::OsString>,
+    home: Option<std::ffi::OsString>,
+) -> Option<PathBuf> {
+    find_project_config(search_start).or_else(|| {
+        if let Some(path) = config_path.filter(|p| !p.is_empty()) {
+            return Some(PathBuf::from(path));
+        }
+        let dir = user_config_dir(xdg_config_home, home)?;
+        find_config_in(&dir)
+    })
+}
+
+fn find_project_config(mut path: &Path) -> Option<PathBuf> {
     if path.is_file() {
         path = path.parent()?;
     }
 
     loop {
-        let mut config_path = path.join("");
-        for name in CONFIG_FILE_NAMES {
-            config_path.set_file_name(name);
-            if config_path.is_file() {
-                return Some(config_path);
-            }
+        if let Some(config_path) = find_config_in(path) {
+            return Some(config_path);
         }
 
         path = path.parent()?;
     }
 }
 
+fn find_config_in(dir: &Path) -> Option<PathBuf> {
+    CONFIG_FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+fn user_config_dir(
+    xdg_config_home: Option<std::ffi::OsString>,
+    home: Option<std::ffi::OsString>,
+) -> Option<PathBuf> {
+    // Per the XDG spec, relative paths are invalid and should be ignored
+    let base = xdg_config_home
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| {
+            home.filter(|h| !h.is_empty())
+                .map(|h| PathBuf::from(h).join(".config"))
+        })?;
+    Some(base.join(USER_CONFIG_DIR_NAME))
+}
+
 const CONFIG_FILE_NAMES: [&str; 2] = [".rustfmt.toml", "rustfmt.toml"];
+const USER_CONFIG_DIR_NAME: &str = "cargofmt";
 
 #[cfg(test)]
 mod test {
@@ -134,4 +606,448 @@ mod test {
             toml::de::from_str("max_width = 100\nuse_small_heuristics = \"Off\"").unwrap();
         assert_eq!(config.array_width(), 0); // always vertical
     }
+
+    #[test]
+    fn style_edition() {
+        assert_eq!(
+            Config::default().style_edition,
+            options::StyleEdition::Edition2024
+        );
+
+        let config: Config = toml::de::from_str(r#"style_edition = "2021""#).unwrap();
+        assert_eq!(config.style_edition, options::StyleEdition::Edition2021);
+
+        // rustfmt's deprecated spelling
+        let config: Config = toml::de::from_str(r#"version = "One""#).unwrap();
+        assert_eq!(config.style_edition, options::StyleEdition::Edition2015);
+        let config: Config = toml::de::from_str(r#"version = "Two""#).unwrap();
+        assert_eq!(config.style_edition, options::StyleEdition::Edition2024);
+    }
+
+    #[test]
+    fn required_version_matches() {
+        let config = Config::default();
+        assert!(config.check_required_version().is_ok());
+
+        let mut config: Config = toml::de::from_str(&format!(
+            "cargofmt_required_version = \"={}\"",
+            env!("CARGO_PKG_VERSION")
+        ))
+        .unwrap();
+        assert!(config.check_required_version().is_ok());
+
+        config.cargofmt_required_version = Some(semver::VersionReq::STAR);
+        assert!(config.check_required_version().is_ok());
+    }
+
+    #[test]
+    fn required_version_mismatch() {
+        let mut config: Config =
+            toml::de::from_str(r#"cargofmt_required_version = "<0.0.1""#).unwrap();
+        let err = config.check_required_version().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "config requires cargo-cargofmt `<0.0.1` but {} is installed",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+
+        config.source = Some(PathBuf::from("/ws/rustfmt.toml"));
+        let err = config.check_required_version().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "`/ws/rustfmt.toml` requires cargo-cargofmt `<0.0.1` but {} is installed",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn required_version_invalid() {
+        let err = toml::de::from_str::<Config>(r#"cargofmt_required_version = "a.b""#).unwrap_err();
+        assert!(
+            err.to_string().contains("cargofmt_required_version"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn rustfmt_required_version() {
+        let config: Config = toml::de::from_str(r#"required_version = "<0.0.1""#).unwrap();
+        assert!(config.check_required_version().is_ok());
+    }
+
+    #[test]
+    fn passes_follow_style_edition() {
+        let config = Config::default();
+        assert!(config.normalize_strings);
+        assert!(config.normalize_datetime_separators());
+        assert!(config.remove_unused_parent_tables());
+        assert!(config.reflow_arrays());
+
+        let config: Config = toml::de::from_str(r#"style_edition = "2021""#).unwrap();
+        assert!(config.normalize_strings);
+        assert!(!config.normalize_datetime_separators());
+        assert!(!config.remove_unused_parent_tables());
+        assert!(!config.reflow_arrays());
+    }
+
+    #[test]
+    fn passes_explicitly_set() {
+        let config: Config =
+            toml::de::from_str("normalize_strings = false\nremove_unused_parent_tables = false")
+                .unwrap();
+        assert!(!config.normalize_strings);
+        assert!(config.normalize_datetime_separators());
+        assert!(!config.remove_unused_parent_tables());
+        assert!(config.reflow_arrays());
+
+        let config: Config = toml::de::from_str(
+            "style_edition = \"2021\"\nreflow_arrays = true\nnormalize_strings = false",
+        )
+        .unwrap();
+        assert!(!config.normalize_strings);
+        assert!(!config.normalize_datetime_separators());
+        assert!(!config.remove_unused_parent_tables());
+        assert!(config.reflow_arrays());
+    }
+
+    /// Creates `files` under a fresh directory for `name`, returning it
+    #[track_caller]
+    fn config_dirs(name: &str, files: &[&str]) -> PathBuf {
+        let root = std::env::temp_dir()
+            .join(format!("cargofmt-{}", std::process::id()))
+            .join(name);
+        let _ = std::fs::remove_dir_all(&root);
+        for file in ["project/crate/Cargo.toml"].iter().chain(files) {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn find_config_prefers_project_config() {
+        let root = config_dirs(
+            "prefers_project",
+            &[
+                "project/rustfmt.toml",
+                "env/rustfmt.toml",
+                "xdg/cargofmt/rustfmt.toml",
+            ],
+        );
+        let found = find_config_with_env(
+            &root.join("project/crate/Cargo.toml"),
+            Some(root.join("env/rustfmt.toml").into()),
+            Some(root.join("xdg").into()),
+            None,
+        );
+        assert_eq!(found, Some(root.join("project/rustfmt.toml")));
+    }
+
+    #[test]
+    fn find_config_prefers_nearest_project_config() {
+        let root = config_dirs(
+            "nearest_project",
+            &["project/rustfmt.toml", "project/crate/.rustfmt.toml"],
+        );
+        let found = find_config_with_env(&root.join("project/crate/Cargo.toml"), None, None, None);
+        assert_eq!(found, Some(root.join("project/crate/.rustfmt.toml")));
+    }
+
+    #[test]
+    fn find_config_prefers_env_over_user_config() {
+        let root = config_dirs(
+            "prefers_env",
+            &["env/rustfmt.toml", "xdg/cargofmt/rustfmt.toml"],
+        );
+        let search_start = root.join("project/crate/Cargo.toml");
+        let found = find_config_with_env(
+            &search_start,
+            Some(root.join("env/rustfmt.toml").into()),
+            Some(root.join("xdg").into()),
+            None,
+        );
+        assert_eq!(found, Some(root.join("env/rustfmt.toml")));
+
+        let found = find_config_with_env(
+            &search_start,
+            Some("".into()),
+            Some(root.join("xdg").into()),
+            None,
+        );
+        assert_eq!(found, Some(root.join("xdg/cargofmt/rustfmt.toml")));
+    }
+
+    #[test]
+    fn find_config_falls_back_to_user_config() {
+        let root = config_dirs(
+            "user_config",
+            &[
+                "xdg/cargofmt/.rustfmt.toml",
+                "home/.config/cargofmt/rustfmt.toml",
+            ],
+        );
+        let search_start = root.join("project/crate/Cargo.toml");
+        let found = find_config_with_env(
+            &search_start,
+            None,
+            Some(root.join("xdg").into()),
+            Some(root.join("home").into()),
+        );
+        assert_eq!(found, Some(root.join("xdg/cargofmt/.rustfmt.toml")));
+
+        let found = find_config_with_env(&search_start, None, None, Some(root.join("home").into()));
+        assert_eq!(found, Some(root.join("home/.config/cargofmt/rustfmt.toml")));
+
+        let found = find_config_with_env(&search_start, None, None, None);
+        assert_eq!(found, None);
+    }
+
+    #[test]
+    fn user_config_dir_prefers_xdg() {
+        assert_eq!(
+            user_config_dir(Some("/xdg".into()), Some("/home/me".into())),
+            Some(PathBuf::from("/xdg/cargofmt"))
+        );
+    }
+
+    #[test]
+    fn user_config_dir_falls_back_to_home() {
+        assert_eq!(
+            user_config_dir(None, Some("/home/me".into())),
+            Some(PathBuf::from("/home/me/.config/cargofmt"))
+        );
+        assert_eq!(
+            user_config_dir(Some("relative".into()), Some("/home/me".into())),
+            Some(PathBuf::from("/home/me/.config/cargofmt"))
+        );
+        assert_eq!(user_config_dir(None, None), None);
+        assert_eq!(user_config_dir(None, Some("".into())), None);
+    }
+
+    #[test]
+    fn ignore_relative_to_config_file() {
+        let mut config: Config =
+            toml::de::from_str(r#"ignore = ["vendor", "third_party/**/Cargo.toml"]"#).unwrap();
+        config.source = Some(PathBuf::from("/ws/rustfmt.toml"));
+
+        assert!(config.is_ignored(Path::new("/ws/vendor/foo/Cargo.toml")));
+        assert!(config.is_ignored(Path::new("/ws/third_party/a/b/Cargo.toml")));
+        assert!(!config.is_ignored(Path::new("/ws/Cargo.toml")));
+        assert!(!config.is_ignored(Path::new("/ws/crates/vendor-lib/Cargo.toml")));
+        assert!(!config.is_ignored(Path::new("/other/vendor/Cargo.toml")));
+    }
+
+    #[test]
+    fn ignore_relative_config_file() {
+        let mut config: Config = toml::de::from_str(r#"ignore = ["vendor"]"#).unwrap();
+        config.source = Some(PathBuf::from("rustfmt.toml"));
+        let current_dir = std::env::current_dir().unwrap();
+
+        assert!(config.is_ignored(&current_dir.join("vendor/foo/Cargo.toml")));
+        assert!(config.is_ignored(Path::new("vendor/foo/Cargo.toml")));
+        assert!(!config.is_ignored(&current_dir.join("Cargo.toml")));
+    }
+
+    #[test]
+    fn ignore_invalid_glob() {
+        let err = toml::de::from_str::<Config>(r#"ignore = ["a/***"]"#).unwrap_err();
+        let err = err.to_string();
+        assert!(err.contains("invalid glob `a/***`"), "{err}");
+        assert!(err.contains("ignore"), "{err}");
+    }
+
+    #[test]
+    fn ignore_without_config_file() {
+        let config: Config = toml::de::from_str(r#"ignore = ["*"]"#).unwrap();
+        assert!(!config.is_ignored(Path::new("/ws/Cargo.toml")));
+    }
+
+    #[track_caller]
+    fn overrides(file: &str, values: &[&str]) -> Result<Config, io::Error> {
+        let mut table = toml::de::from_str(file).unwrap();
+        let values = values
+            .iter()
+            .map(|v| ConfigOverrides::parse_value(v).unwrap())
+            .collect::<Vec<_>>();
+        apply_overrides(&mut table, &values, &mut Vec::new())?;
+        Config::deserialize(table).map_err(io::Error::other)
+    }
+
+    #[test]
+    fn parse_override_value() {
+        assert_eq!(
+            ConfigOverrides::parse_value("max_width=80"),
+            Ok(("max_width".to_owned(), "80".to_owned()))
+        );
+        assert_eq!(
+            ConfigOverrides::parse_value(" tab_spaces = 2 "),
+            Ok(("tab_spaces".to_owned(), "2".to_owned()))
+        );
+        assert!(ConfigOverrides::parse_value("max_width").is_err());
+        assert!(ConfigOverrides::parse_value("=80").is_err());
+    }
+
+    #[test]
+    fn override_layers_over_file() {
+        let config = overrides("max_width = 80\ntab_spaces = 2", &["max_width=120"]).unwrap();
+        assert_eq!(config.max_width, 120);
+        assert_eq!(config.tab_spaces, 2);
+    }
+
+    #[test]
+    fn override_last_wins() {
+        let config = overrides("", &["max_width=120", "max_width=90"]).unwrap();
+        assert_eq!(config.max_width, 90);
+    }
+
+    #[test]
+    fn override_unquoted_string() {
+        let config = overrides(
+            "",
+            &["trailing_comma=Never", "use_small_heuristics=\"Max\""],
+        )
+        .unwrap();
+        assert!(matches!(
+            config.trailing_comma,
+            lists::SeparatorTactic::Never
+        ));
+        assert!(matches!(
+            config.use_small_heuristics,
+            options::UseSmallHeuristics::Max
+        ));
+    }
+
+    #[test]
+    fn override_string_like_number() {
+        let config = overrides("", &["style_edition=2021"]).unwrap();
+        assert_eq!(config.style_edition, options::StyleEdition::Edition2021);
+
+        let err = overrides("", &["max_width=-1"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid `--config max_width=-1`: invalid value: integer `-1`, expected usize\nin `max_width`\n"
+        );
+    }
+
+    #[test]
+    fn override_unknown_key() {
+        let mut table = toml::Table::new();
+        let mut warnings = Vec::new();
+        let values = [("max_widht".to_owned(), "80".to_owned())];
+        apply_overrides(&mut table, &values, &mut warnings).unwrap();
+        assert_eq!(
+            warnings,
+            ["unknown configuration option `max_widht` in `--config max_widht=80`"]
+        );
+    }
+
+    #[test]
+    fn override_invalid_value() {
+        let err = overrides("", &["max_width=wide"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid `--config max_width=wide`: invalid type: string \"wide\", expected usize\nin `max_width`\n"
+        );
+    }
+
+    #[track_caller]
+    fn path_overrides(file: &str, manifest: &str) -> Result<Config, io::Error> {
+        let mut table = toml::de::from_str(file).unwrap();
+        apply_path_overrides(
+            &mut table,
+            Path::new("/ws/rustfmt.toml"),
+            Path::new(manifest),
+            &mut Vec::new(),
+        )?;
+        Config::deserialize(table).map_err(io::Error::other)
+    }
+
+    const PATH_OVERRIDES: &str = r#"
+max_width = 100
+tab_spaces = 2
+
+[[overrides]]
+paths = ["examples/*"]
+max_width = 80
+
+[[overrides]]
+paths = ["tests/fixtures/**"]
+disable_all_formatting = true
+
+[[overrides]]
+paths = ["examples/narrow/Cargo.toml"]
+max_width = 60
+"#;
+
+    #[test]
+    fn path_override_no_match() {
+        let config = path_overrides(PATH_OVERRIDES, "/ws/crates/foo/Cargo.toml").unwrap();
+        assert_eq!(config.max_width, 100);
+        assert!(!config.disable_all_formatting);
+    }
+
+    #[test]
+    fn path_override_matches_directory() {
+        let config = path_overrides(PATH_OVERRIDES, "/ws/examples/wide/Cargo.toml").unwrap();
+        assert_eq!(config.max_width, 80);
+        assert_eq!(config.tab_spaces, 2);
+    }
+
+    #[test]
+    fn path_override_matches_recursively() {
+        let config = path_overrides(PATH_OVERRIDES, "/ws/tests/fixtures/a/b/Cargo.toml").unwrap();
+        assert!(config.disable_all_formatting);
+    }
+
+    #[test]
+    fn path_override_later_wins() {
+        let config = path_overrides(PATH_OVERRIDES, "/ws/examples/narrow/Cargo.toml").unwrap();
+        assert_eq!(config.max_width, 60);
+    }
+
+    #[test]
+    fn path_override_outside_config_dir() {
+        let config = path_overrides(PATH_OVERRIDES, "/other/examples/wide/Cargo.toml").unwrap();
+        assert_eq!(config.max_width, 100);
+    }
+
+    #[test]
+    fn path_override_unknown_key() {
+        let mut table =
+            toml::de::from_str("[[overrides]]\npaths = [\"*\"]\nmax_widht = 80").unwrap();
+        let mut warnings = Vec::new();
+        apply_path_overrides(
+            &mut table,
+            Path::new("/ws/rustfmt.toml"),
+            Path::new("/ws/Cargo.toml"),
+            &mut warnings,
+        )
+        .unwrap();
+        assert_eq!(
+            warnings,
+            ["unknown configuration option `max_widht` in `overrides[0]`"]
+        );
+    }
+
+    #[test]
+    fn path_override_missing_paths() {
+        let err = path_overrides("[[overrides]]\nmax_width = 80", "/ws/Cargo.toml").unwrap_err();
+        assert_eq!(err.to_string(), "`overrides[0]` is missing `paths`");
+    }
+
+    #[test]
+    fn path_override_invalid_glob() {
+        let err =
+            path_overrides("[[overrides]]\npaths = [\"a/***\"]", "/ws/Cargo.toml").unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("invalid glob `a/***` in `overrides[0].paths`"),
+            "{err}"
+        );
+    }
 }
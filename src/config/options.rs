@@ -22,3 +22,37 @@ pub enum UseSmallHeuristics {
     /// Use `max_width` for all width settings.
     Max,
 }
+
+/// Which generation of formatting rules to apply.
+///
+/// Uses the same values as rustfmt so config files can be shared.  The default never
+/// changes; behavior that would change existing output is only enabled by newer
+/// style editions.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Deserialize)]
+pub enum StyleEdition {
+    /// Formatting as of cargo-cargofmt 0.1.1
+    #[serde(rename = "2015", alias = "One")]
+    Edition2015,
+    /// Same as [`StyleEdition::Edition2015`]
+    #[serde(rename = "2018")]
+    Edition2018,
+    /// Same as [`StyleEdition::Edition2015`]
+    #[serde(rename = "2021")]
+    Edition2021,
+    /// Normalizes date-time separators, removes unused parent tables, and reflows arrays
+    #[default]
+    #[serde(rename = "2024", alias = "Two")]
+    Edition2024,
+}
+
+/// How to write dependencies defined by dotted keys, like `serde.workspace = true`
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, serde::Deserialize)]
+pub enum DottedKeys {
+    /// Leave dotted keys as they are.
+    #[default]
+    Preserve,
+    /// Merge dotted keys into an inline table, like `serde = { workspace = true }`.
+    InlineTables,
+    /// Move dotted keys into a table of their own, like `[dependencies.serde]`.
+    StandardTables,
+}
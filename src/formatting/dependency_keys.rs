@@ -0,0 +1,239 @@
+use std::ops::Range;
+
+use super::manifest::is_dependency_subtable;
+use super::manifest::is_dependency_table;
+use super::manifest::key_rank;
+use crate::toml::Entry;
+use crate::toml::Table;
+use crate::toml::TokenKind;
+use crate::toml::TomlTokens;
+
+/// Sorts the keys of dependency specifications into `order`.
+///
+/// This covers inline tables, like `serde = { features = ["derive"], version = "1" }`,
+/// and subtables, like `[dependencies.serde]`.  Keys not in `order` take the place of
+/// `*`, or go last without it, keeping their relative order.
+///
+/// Inline tables with comments inside are left alone.
+#[tracing::instrument]
+pub fn reorder_dependency_keys(tokens: &mut TomlTokens<'_>, order: &[String]) {
+    let rank = |entry: &Entry| key_rank(order, &entry.key()[0]);
+
+    let tables = Table::new(tokens);
+    // Back to front so earlier spans stay valid
+    for table in tables.iter().rev() {
+        if table.is_array_table() {
+            continue;
+        }
+
+        if is_dependency_table(table.name()) {
+            let entries = Entry::parse(tokens, table.body_span());
+            for entry in entries.iter().rev() {
+                let open = entry.value_span().start;
+                if entry.key().len() == 1
+                    && tokens.tokens.get(open).map(|t| t.kind) == Some(TokenKind::InlineTableOpen)
+                {
+                    sort_inline_table(tokens, open, entry.value_span(), rank);
+                }
+            }
+        } else if is_dependency_subtable(table.name()) {
+            let entries = Entry::parse(tokens, table.body_span());
+            for run in Entry::runs(&entries).into_iter().rev() {
+                Entry::sort_by(tokens, run, |a, b| rank(a).cmp(&rank(b)));
+            }
+        }
+    }
+}
+
+/// Stable-sorts the key/value pairs of the inline table at `open`, keeping separators in place
+fn sort_inline_table(
+    tokens: &mut TomlTokens<'_>,
+    open: usize,
+    span: Range<usize>,
+    rank: impl Fn(&Entry) -> usize,
+) {
+    let has_comment = tokens.tokens[span]
+        .iter()
+        .any(|t| t.kind == TokenKind::Comment);
+    if has_comment {
+        return;
+    }
+
+    let entries = Entry::parse_inline(tokens, open);
+    let mut order = (0..entries.len()).collect::<Vec<_>>();
+    order.sort_by_key(|i| rank(&entries[*i]));
+    if order.iter().enumerate().all(|(i, o)| i == *o) {
+        return;
+    }
+
+    let (Some(first), Some(last)) = (entries.first(), entries.last()) else {
+        return;
+    };
+    let mut reordered = Vec::new();
+    for (slot, &from) in order.iter().enumerate() {
+        reordered.extend(tokens.tokens[entries[from].span()].iter().cloned());
+        if let Some(next) = entries.get(slot + 1) {
+            let separator = entries[slot].span().end..next.span().start;
+            reordered.extend(tokens.tokens[separator].iter().cloned());
+        }
+    }
+    tokens
+        .tokens
+        .splice(first.span().start..last.span().end, reordered);
+}
+
+#[cfg(test)]
+mod test {
+    use snapbox::IntoData;
+    use snapbox::assert_data_eq;
+    use snapbox::str;
+
+    #[track_caller]
+    fn valid(input: &str, order: &[&str], expected: impl IntoData) {
+        let order = order.iter().map(|o| (*o).to_owned()).collect::<Vec<_>>();
+        let mut tokens = crate::toml::TomlTokens::parse(input);
+        super::reorder_dependency_keys(&mut tokens, &order);
+        let actual = tokens.to_string();
+
+        assert_data_eq!(&actual, expected);
+
+        let (_, errors) = toml::de::DeTable::parse_recoverable(&actual);
+        if !errors.is_empty() {
+            use std::fmt::Write as _;
+            let mut result = String::new();
+            writeln!(&mut result, "---").unwrap();
+            for error in errors {
+                writeln!(&mut result, "{error}").unwrap();
+                writeln!(&mut result, "---").unwrap();
+            }
+            panic!("failed to parse\n---\n{actual}\n{result}");
+        }
+    }
+
+    const DEFAULT: &[&str] = &[
+        "workspace",
+        "version",
+        "path",
+        "git",
+        "branch",
+        "tag",
+        "rev",
+        "registry",
+        "package",
+        "default-features",
+        "features",
+        "optional",
+        "public",
+    ];
+
+    #[test]
+    fn empty() {
+        valid("", DEFAULT, str![]);
+    }
+
+    #[test]
+    fn inline_tables() {
+        valid(
+            r#"
+[dependencies]
+serde = { features = ["derive"], version = "1", optional = true, path = "../serde" }
+toml = {optional=true,version="1"}
+anyhow = "1"
+regex = { foo = 1, version = "1", bar = 2 }
+
+[target.'cfg(unix)'.dev-dependencies]
+libc = { default-features = false, workspace = true }
+"#,
+            DEFAULT,
+            str![[r#"
+
+[dependencies]
+serde = { version = "1", path = "../serde", features = ["derive"], optional = true }
+toml = {version="1",optional=true}
+anyhow = "1"
+regex = { version = "1", foo = 1, bar = 2 }
+
+[target.'cfg(unix)'.dev-dependencies]
+libc = { workspace = true, default-features = false }
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn subtables() {
+        valid(
+            r#"
+[dependencies.serde]
+# Needed for derives
+features = ["derive"]
+optional = true # for now
+version = "1"
+
+git = "https://github.com/serde-rs/serde"
+branch = "master"
+
+[build-dependencies.cc]
+version = "1"
+"#,
+            DEFAULT,
+            str![[r#"
+
+[dependencies.serde]
+version = "1"
+# Needed for derives
+features = ["derive"]
+optional = true # for now
+
+git = "https://github.com/serde-rs/serde"
+branch = "master"
+
+[build-dependencies.cc]
+version = "1"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn other_keys_placeholder() {
+        valid(
+            r#"
+[dependencies]
+serde = { optional = true, foo = 1, version = "1" }
+"#,
+            &["version", "*", "optional"],
+            str![[r#"
+
+[dependencies]
+serde = { version = "1", foo = 1, optional = true }
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_other_tables() {
+        valid(
+            r#"
+[package.metadata]
+serde = { optional = true, version = "1" }
+
+[dependencies.serde.nested]
+optional = true
+version = "1"
+"#,
+            DEFAULT,
+            str![[r#"
+
+[package.metadata]
+serde = { optional = true, version = "1" }
+
+[dependencies.serde.nested]
+optional = true
+version = "1"
+
+"#]],
+        );
+    }
+}
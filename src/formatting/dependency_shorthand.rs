@@ -0,0 +1,163 @@
+use super::manifest::is_dependency_table;
+use crate::toml::Entry;
+use crate::toml::Table;
+use crate::toml::TokenKind;
+use crate::toml::TomlTokens;
+
+/// Collapses `foo = { version = "1.0" }` dependencies to `foo = "1.0"`.
+///
+/// Inline tables with any other key, or with comments inside, are left alone.
+#[tracing::instrument]
+pub fn collapse_version_only_dependencies(tokens: &mut TomlTokens<'_>) {
+    let tables = Table::new(tokens);
+    // Back to front so earlier spans stay valid
+    for table in tables.iter().rev() {
+        if table.is_array_table() || !is_dependency_table(table.name()) {
+            continue;
+        }
+
+        let entries = Entry::parse(tokens, table.body_span());
+        for entry in entries.iter().rev() {
+            let value_span = entry.value_span();
+            if entry.key().len() != 1
+                || tokens.tokens.get(value_span.start).map(|t| t.kind)
+                    != Some(TokenKind::InlineTableOpen)
+            {
+                continue;
+            }
+            let has_comment = tokens.tokens[value_span.clone()]
+                .iter()
+                .any(|t| t.kind == TokenKind::Comment);
+            if has_comment {
+                continue;
+            }
+            let inline = Entry::parse_inline(tokens, value_span.start);
+            let [version] = inline.as_slice() else {
+                continue;
+            };
+            let version_span = version.value_span();
+            if version.key() != ["version"]
+                || version_span.len() != 1
+                || tokens.tokens[version_span.start].kind != TokenKind::Scalar
+            {
+                continue;
+            }
+
+            let scalar = tokens.tokens[version_span.start].clone();
+            tokens.tokens.splice(value_span, [scalar]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use snapbox::IntoData;
+    use snapbox::assert_data_eq;
+    use snapbox::str;
+
+    #[track_caller]
+    fn valid(input: &str, expected: impl IntoData) {
+        let mut tokens = crate::toml::TomlTokens::parse(input);
+        super::collapse_version_only_dependencies(&mut tokens);
+        let actual = tokens.to_string();
+
+        assert_data_eq!(&actual, expected);
+
+        let (_, errors) = toml::de::DeTable::parse_recoverable(&actual);
+        if !errors.is_empty() {
+            use std::fmt::Write as _;
+            let mut result = String::new();
+            writeln!(&mut result, "---").unwrap();
+            for error in errors {
+                writeln!(&mut result, "{error}").unwrap();
+                writeln!(&mut result, "---").unwrap();
+            }
+            panic!("failed to parse\n---\n{actual}\n{result}");
+        }
+    }
+
+    #[test]
+    fn empty() {
+        valid("", str![]);
+    }
+
+    #[test]
+    fn collapse() {
+        valid(
+            r#"
+[dependencies]
+anyhow = { version = "1.0" } # trailing
+serde = {version='1',}
+toml = { version = "1", features = ["serde"] }
+regex = "1"
+
+[dev-dependencies]
+snapbox = { version = "0.6" }
+"#,
+            str![[r#"
+
+[dependencies]
+anyhow = "1.0" # trailing
+serde = '1'
+toml = { version = "1", features = ["serde"] }
+regex = "1"
+
+[dev-dependencies]
+snapbox = "0.6"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn collapse_target_and_workspace() {
+        valid(
+            r#"
+[target.'cfg(unix)'.build-dependencies]
+cc = { version = "1" }
+
+[workspace.dependencies]
+serde = { version = "1" }
+"#,
+            str![[r#"
+
+[target.'cfg(unix)'.build-dependencies]
+cc = "1"
+
+[workspace.dependencies]
+serde = "1"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_other_tables() {
+        valid(
+            r#"
+[package.metadata]
+foo = { version = "1" }
+
+[dependencies.serde]
+foo = { version = "1" }
+
+[dependencies]
+bar = { workspace = true }
+qux.version = "1"
+"#,
+            str![[r#"
+
+[package.metadata]
+foo = { version = "1" }
+
+[dependencies.serde]
+foo = { version = "1" }
+
+[dependencies]
+bar = { workspace = true }
+qux.version = "1"
+
+"#]],
+        );
+    }
+}
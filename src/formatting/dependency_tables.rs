@@ -0,0 +1,573 @@
+use std::ops::Range;
+
+use unicode_width::UnicodeWidthStr as _;
+
+use super::manifest::is_dependency_table;
+use super::skip_tables::SkipTables;
+use crate::toml::Entry;
+use crate::toml::Table;
+use crate::toml::TokenKind;
+use crate::toml::TomlTokens;
+
+/// Converts between `[dependencies.foo]` subtables and `foo = { ... }` inline tables.
+///
+/// - Subtables become inline tables when the line fits within `width`
+/// - Inline tables longer than `width` become subtables
+///
+/// Leading comments and comments on the header or inline table's line move along.
+/// Tables with comments anywhere else are left alone as there is nowhere to put them, as
+/// are tables in `skip_tables`.
+#[tracing::instrument]
+pub fn convert_dependency_tables(
+    tokens: &mut TomlTokens<'_>,
+    width: usize,
+    skip_tables: &[String],
+) {
+    let skip = SkipTables::new(skip_tables);
+    // Every conversion removes a candidate, so these terminate
+    while inline_next_subtable(tokens, width, &skip) {}
+    while expand_next_inline_table(tokens, width, &skip) {}
+}
+
+fn inline_next_subtable(tokens: &mut TomlTokens<'_>, width: usize, skip: &SkipTables) -> bool {
+    let tables = Table::new(tokens);
+    for table in &tables {
+        let Some((_, parent_name)) = table.name().split_last() else {
+            continue;
+        };
+        if table.is_array_table()
+            || !is_dependency_table(parent_name)
+            || skip.contains(table.name())
+        {
+            continue;
+        }
+        let has_subtables = tables
+            .iter()
+            .any(|t| table.name().len() < t.name().len() && t.name().starts_with(table.name()));
+        if has_subtables {
+            continue;
+        }
+        let Some(value) = render_inline_dependency(tokens, table) else {
+            continue;
+        };
+        let header_keys = header_keys(tokens, table.header_span());
+        let (dep_key, parent_keys) = header_keys.split_last().expect("same length as name");
+        // Measured like `expand_next_inline_table` does, so the result stays put
+        let line = format!("{dep_key} = {value}");
+        if width < line.width() {
+            continue;
+        }
+
+        let comment = trailing_comment(tokens, table.header_span().end..table.body_span().start);
+        let leading = raw(tokens, table.span().start..table.header_span().start);
+        let line = format!("{leading}{line}{comment}\n");
+
+        let entries = Entry::parse(tokens, table.body_span());
+        let table_end = entries
+            .last()
+            .map(|e| e.span().end)
+            .unwrap_or(table.body_span().start);
+        let parent = tables
+            .iter()
+            .find(|t| !t.is_array_table() && t.name() == parent_name);
+        match parent {
+            Some(parent) => {
+                let parent_entries = Entry::parse(tokens, parent.body_span());
+                let at = parent_entries
+                    .last()
+                    .map(|e| e.span().end)
+                    .unwrap_or(parent.body_span().start);
+                let line = if ends_line(tokens, at) {
+                    line
+                } else {
+                    format!("\n{line}")
+                };
+                let removed = table.span().start..table.body_span().end;
+                if removed.end <= at {
+                    insert(tokens, at, &line);
+                    tokens.tokens.drain(removed);
+                } else {
+                    tokens.tokens.drain(removed);
+                    insert(tokens, at, &line);
+                }
+            }
+            None => {
+                // Without a parent, the subtable becomes it
+                let header = format!("[{}]\n", parent_keys.join("."));
+                let line = line.strip_prefix(&leading).unwrap_or(&line);
+                let text = format!("{leading}{header}{line}");
+                let replaced = table.span().start..table_end;
+                let text = if ends_line(tokens, table_end) {
+                    text
+                } else {
+                    text.trim_end_matches('\n').to_owned()
+                };
+                tokens.tokens.drain(replaced.clone());
+                insert(tokens, replaced.start, &text);
+            }
+        }
+        return true;
+    }
+    false
+}
+
+fn expand_next_inline_table(tokens: &mut TomlTokens<'_>, width: usize, skip: &SkipTables) -> bool {
+    let tables = Table::new(tokens);
+    for table in &tables {
+        if table.is_array_table() || !is_dependency_table(table.name()) {
+            continue;
+        }
+
+        let entries = Entry::parse(tokens, table.body_span());
+        for entry in &entries {
+            let open = entry.value_span().start;
+            if entry.key().len() != 1
+                || tokens.tokens[open].kind != TokenKind::InlineTableOpen
+                || skip.contains(&[table.name(), entry.key()].concat())
+            {
+                continue;
+            }
+            let is_multiline = tokens.tokens[entry.value_span()]
+                .iter()
+                .any(|t| matches!(t.kind, TokenKind::Newline | TokenKind::Comment));
+            if is_multiline {
+                continue;
+            }
+            let line = raw(tokens, entry.key_span().start..entry.value_span().end);
+            if line.width() <= width {
+                continue;
+            }
+
+            let leading = raw(tokens, entry.span().start..entry.key_span().start);
+            let leading = leading.trim_end_matches([' ', '\t']);
+            let comment = trailing_comment(tokens, entry.value_span().end..entry.span().end);
+            let mut header_keys = header_keys(tokens, table.header_span());
+            header_keys.push(render_key(tokens, entry.key_span()));
+            let mut text = format!("\n{leading}[{}]{comment}\n", header_keys.join("."));
+            for inline in Entry::parse_inline(tokens, open) {
+                text.push_str(&format!(
+                    "{} = {}\n",
+                    render_key(tokens, inline.key_span()),
+                    render_value(tokens, inline.value_span())
+                ));
+            }
+
+            let at = entries.last().expect("contains `entry`").span().end;
+            let text = if ends_line(tokens, at) {
+                text
+            } else {
+                format!("\n{text}")
+            };
+            insert(tokens, at, &text);
+            tokens.tokens.drain(entry.span());
+            return true;
+        }
+    }
+    false
+}
+
+/// Renders a subtable's body as an inline table, if there are no comments to lose
+fn render_inline_dependency(tokens: &TomlTokens<'_>, table: &Table) -> Option<String> {
+    let entries = Entry::parse(tokens, table.body_span());
+    let mut covered = table.body_span().start;
+    for entry in &entries {
+        let skipped = &tokens.tokens[covered..entry.span().start];
+        if skipped
+            .iter()
+            .any(|t| !matches!(t.kind, TokenKind::Whitespace | TokenKind::Newline))
+        {
+            return None;
+        }
+        if tokens.tokens[entry.span()]
+            .iter()
+            .any(|t| matches!(t.kind, TokenKind::Comment | TokenKind::Error))
+        {
+            return None;
+        }
+        covered = entry.span().end;
+    }
+    let rest = &tokens.tokens[covered..table.body_span().end];
+    if rest
+        .iter()
+        .any(|t| !matches!(t.kind, TokenKind::Whitespace | TokenKind::Newline))
+    {
+        return None;
+    }
+
+    if entries.is_empty() {
+        return Some("{}".to_owned());
+    }
+    let fields = entries
+        .iter()
+        .map(|e| {
+            format!(
+                "{} = {}",
+                render_key(tokens, e.key_span()),
+                render_value(tokens, e.value_span())
+            )
+        })
+        .collect::<Vec<_>>();
+    Some(format!("{{ {} }}", fields.join(", ")))
+}
+
+/// Renders a possibly dotted key without whitespace
+fn render_key(tokens: &TomlTokens<'_>, span: Range<usize>) -> String {
+    tokens.tokens[span]
+        .iter()
+        .filter(|t| t.kind != TokenKind::Whitespace)
+        .map(|t| t.raw.as_ref())
+        .collect()
+}
+
+/// Renders a value on a single line
+fn render_value(tokens: &TomlTokens<'_>, span: Range<usize>) -> String {
+    let significant = tokens.tokens[span]
+        .iter()
+        .filter(|t| !matches!(t.kind, TokenKind::Whitespace | TokenKind::Newline))
+        .collect::<Vec<_>>();
+    let mut rendered = String::new();
+    for (i, token) in significant.iter().enumerate() {
+        let next = significant.get(i + 1).map(|t| t.kind);
+        match token.kind {
+            TokenKind::ValueSep => {
+                if !matches!(
+                    next,
+                    Some(TokenKind::ArrayClose | TokenKind::InlineTableClose)
+                ) {
+                    rendered.push_str(", ");
+                }
+            }
+            TokenKind::KeyValSep => rendered.push_str(" = "),
+            TokenKind::InlineTableOpen if next != Some(TokenKind::InlineTableClose) => {
+                rendered.push_str("{ ");
+            }
+            TokenKind::InlineTableClose
+                if significant[i - 1].kind != TokenKind::InlineTableOpen =>
+            {
+                rendered.push_str(" }");
+            }
+            _ => rendered.push_str(&token.raw),
+        }
+    }
+    rendered
+}
+
+/// The raw keys of a table header
+fn header_keys(tokens: &TomlTokens<'_>, header: Range<usize>) -> Vec<String> {
+    tokens.tokens[header]
+        .iter()
+        .filter(|t| t.kind == TokenKind::SimpleKey)
+        .map(|t| t.raw.to_string())
+        .collect()
+}
+
+/// Renders the comment in `span` as a trailing comment
+fn trailing_comment(tokens: &TomlTokens<'_>, span: Range<usize>) -> String {
+    tokens.tokens[span]
+        .iter()
+        .find(|t| t.kind == TokenKind::Comment)
+        .map(|t| format!(" {}", t.raw))
+        .unwrap_or_default()
+}
+
+fn raw(tokens: &TomlTokens<'_>, span: Range<usize>) -> String {
+    tokens.tokens[span].iter().map(|t| t.raw.as_ref()).collect()
+}
+
+/// Whether `i` is at the start of a line
+fn ends_line(tokens: &TomlTokens<'_>, i: usize) -> bool {
+    i == 0 || tokens.tokens[i - 1].kind == TokenKind::Newline
+}
+
+fn insert(tokens: &mut TomlTokens<'_>, at: usize, text: &str) {
+    tokens
+        .tokens
+        .splice(at..at, TomlTokens::parse_snippet(text));
+}
+
+#[cfg(test)]
+mod test {
+    use snapbox::IntoData;
+    use snapbox::assert_data_eq;
+    use snapbox::str;
+
+    #[track_caller]
+    fn valid(input: &str, width: usize, expected: impl IntoData) {
+        let mut tokens = crate::toml::TomlTokens::parse(input);
+        super::convert_dependency_tables(&mut tokens, width, &[]);
+        let actual = tokens.to_string();
+
+        assert_data_eq!(&actual, expected);
+
+        let (_, errors) = toml::de::DeTable::parse_recoverable(&actual);
+        if !errors.is_empty() {
+            use std::fmt::Write as _;
+            let mut result = String::new();
+            writeln!(&mut result, "---").unwrap();
+            for error in errors {
+                writeln!(&mut result, "{error}").unwrap();
+                writeln!(&mut result, "---").unwrap();
+            }
+            panic!("failed to parse\n---\n{actual}\n{result}");
+        }
+    }
+
+    #[test]
+    fn empty() {
+        valid("", 100, str![]);
+    }
+
+    #[test]
+    fn inline_into_parent() {
+        valid(
+            r#"
+[dependencies]
+anyhow = "1"
+
+[dependencies.serde]
+version = "1"
+features = [
+    "derive",
+]
+
+[dev-dependencies]
+"#,
+            100,
+            str![[r#"
+
+[dependencies]
+anyhow = "1"
+serde = { version = "1", features = ["derive"] }
+
+[dev-dependencies]
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn inline_without_parent() {
+        valid(
+            r#"
+# About serde
+[dependencies.serde] # the best
+version = "1"
+default-features = false
+
+[dependencies.toml]
+version = "1"
+"#,
+            100,
+            str![[r#"
+
+# About serde
+[dependencies]
+serde = { version = "1", default-features = false } # the best
+toml = { version = "1" }
+
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn inline_target_dependencies() {
+        valid(
+            r#"
+[target.'cfg(unix)'.dev-dependencies]
+[target.'cfg(unix)'.dev-dependencies.libc]
+version = "0.2"
+"#,
+            100,
+            str![[r#"
+
+[target.'cfg(unix)'.dev-dependencies]
+libc = { version = "0.2" }
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_subtable_with_comments() {
+        valid(
+            r#"
+[dependencies]
+
+[dependencies.serde]
+# Needed for derives
+version = "1"
+features = [
+    "derive", # derives
+]
+"#,
+            100,
+            str![[r#"
+
+[dependencies]
+
+[dependencies.serde]
+# Needed for derives
+version = "1"
+features = [
+    "derive", # derives
+]
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_subtable_too_wide() {
+        valid(
+            r#"
+[dependencies]
+
+[dependencies.serde]
+version = "1"
+features = ["derive"]
+"#,
+            40,
+            str![[r#"
+
+[dependencies]
+
+[dependencies.serde]
+version = "1"
+features = ["derive"]
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_non_dependency_subtables() {
+        valid(
+            r#"
+[package]
+
+[package.metadata]
+foo = 1
+"#,
+            100,
+            str![[r#"
+
+[package]
+
+[package.metadata]
+foo = 1
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn expand_too_wide() {
+        valid(
+            r#"
+[dependencies]
+anyhow = "1"
+# Serialization
+serde = { version = "1", features = ["derive", "rc"], optional = true } # the best
+toml = "1"
+
+[dev-dependencies]
+"#,
+            40,
+            str![[r#"
+
+[dependencies]
+anyhow = "1"
+toml = "1"
+
+# Serialization
+[dependencies.serde] # the best
+version = "1"
+features = ["derive", "rc"]
+optional = true
+
+[dev-dependencies]
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn expand_at_end_of_file() {
+        valid(
+            r#"[workspace.dependencies]
+serde = { version = "1", features = ["derive"] }"#,
+            20,
+            str![[r#"
+[workspace.dependencies]
+
+
+[workspace.dependencies.serde]
+version = "1"
+features = ["derive"]
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_fitting_inline() {
+        valid(
+            r#"
+[dependencies]
+serde = { version = "1", features = ["derive"] }
+"#,
+            100,
+            str![[r#"
+
+[dependencies]
+serde = { version = "1", features = ["derive"] }
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn idempotent_at_width_boundary() {
+        const INPUT: &str = r#"
+[dependencies]
+anyhow = "1"
+
+[dependencies.some-long-dependency-name]
+version = "1"
+"#;
+        // `some-long-dependency-name = { version = "1" }` is 45 wide
+        for width in [44, 45] {
+            let mut tokens = crate::toml::TomlTokens::parse(INPUT);
+            super::convert_dependency_tables(&mut tokens, width, &[]);
+            let once = tokens.to_string();
+            super::convert_dependency_tables(&mut tokens, width, &[]);
+            assert_eq!(tokens.to_string(), once, "width {width}");
+        }
+        valid(
+            INPUT,
+            44,
+            str![[r#"
+
+[dependencies]
+anyhow = "1"
+
+[dependencies.some-long-dependency-name]
+version = "1"
+
+"#]],
+        );
+        valid(
+            INPUT,
+            45,
+            str![[r#"
+
+[dependencies]
+anyhow = "1"
+some-long-dependency-name = { version = "1" }
+
+
+"#]],
+        );
+    }
+}
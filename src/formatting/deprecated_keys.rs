@@ -0,0 +1,333 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use super::manifest::is_dependency_subtable;
+use super::manifest::is_dependency_table;
+use super::skip_tables::SkipTables;
+use crate::toml::TokenKind;
+use crate::toml::TomlTokens;
+
+/// Rewrites deprecated keys and tables to their modern spellings.
+///
+/// - `[project]` becomes `[package]`
+/// - `dev_dependencies` and `build_dependencies` become `dev-dependencies` and
+///   `build-dependencies`
+/// - `default_features` in dependencies becomes `default-features`
+/// - `crate_type` and `proc_macro` in build targets become `crate-type` and `proc-macro`
+///
+/// Keys are rewritten wherever they appear, whether in table headers, dotted keys or
+/// inline tables.  When both spellings are present in the same table, the deprecated
+/// one is left alone and reported in `warnings`.  Keys in or holding `skip_tables` are
+/// left alone too.
+#[tracing::instrument(skip(warnings))]
+pub fn rewrite_deprecated_keys(
+    tokens: &mut TomlTokens<'_>,
+    skip_tables: &[String],
+    warnings: &mut Vec<String>,
+) {
+    let skip = SkipTables::new(skip_tables);
+    let keys = key_paths(tokens);
+    let present = keys.iter().map(|(_, path)| path).collect::<HashSet<_>>();
+
+    let mut reported = HashSet::new();
+    let mut renames = Vec::new();
+    for (i, path) in &keys {
+        let Some(modern) = modern_name(path) else {
+            continue;
+        };
+        let (deprecated, parent) = path.split_last().expect("keys are non-empty");
+        let mut sibling = parent.to_vec();
+        sibling.push(modern.to_owned());
+        if skip.overlaps(path) || skip.overlaps(&sibling) {
+            continue;
+        }
+        if present.contains(&sibling) {
+            if reported.insert(path) {
+                warnings.push(format!(
+                    "both `{}` and `{}` are present, leaving `{deprecated}` as is",
+                    path.join("."),
+                    sibling.join(".")
+                ));
+            }
+            continue;
+        }
+        renames.push((*i, modern));
+    }
+
+    for (i, modern) in renames {
+        let token = &mut tokens.tokens[i];
+        token.raw = Cow::Borrowed(modern);
+        token.decoded = Some(Cow::Borrowed(modern));
+        token.encoding = None;
+    }
+}
+
+/// The modern spelling of the last key in `path`, if it is deprecated
+fn modern_name(path: &[String]) -> Option<&'static str> {
+    let (key, parent) = path.split_last()?;
+    let modern = match key.as_str() {
+        "project" => "package",
+        "dev_dependencies" => "dev-dependencies",
+        "build_dependencies" => "build-dependencies",
+        "default_features" => "default-features",
+        "crate_type" => "crate-type",
+        "proc_macro" => "proc-macro",
+        _ => return None,
+    };
+    let is_deprecated = match modern {
+        "package" => parent.is_empty(),
+        "dev-dependencies" | "build-dependencies" => is_dependency_table(path),
+        "default-features" => is_dependency_subtable(parent),
+        "crate-type" => parent == ["lib"] || parent == ["example"],
+        "proc-macro" => parent == ["lib"],
+        _ => false,
+    };
+    is_deprecated.then_some(modern)
+}
+
+/// Every key, from headers, dotted keys and inline tables, along with its full path
+///
+/// Array tables and arrays of tables don't add to the path.
+fn key_paths(tokens: &TomlTokens<'_>) -> Vec<(usize, Vec<String>)> {
+    let mut keys = Vec::new();
+    let mut table = Vec::new();
+    let mut in_header = false;
+    // Path of the inline tables and arrays being parsed
+    let mut containers = Vec::<Vec<String>>::new();
+    let mut key = Vec::new();
+    let mut is_key_start = true;
+
+    for (i, token) in tokens.tokens.iter().enumerate() {
+        match token.kind {
+            TokenKind::StdTableOpen | TokenKind::ArrayTableOpen => {
+                in_header = true;
+                table.clear();
+            }
+            TokenKind::StdTableClose | TokenKind::ArrayTableClose => {
+                in_header = false;
+                is_key_start = true;
+            }
+            TokenKind::SimpleKey => {
+                let name = token.decoded.as_ref().unwrap_or(&token.raw).to_string();
+                if in_header {
+                    table.push(name);
+                    keys.push((i, table.clone()));
+                } else {
+                    if is_key_start {
+                        key = containers.last().unwrap_or(&table).clone();
+                        is_key_start = false;
+                    }
+                    key.push(name);
+                    keys.push((i, key.clone()));
+                }
+            }
+            TokenKind::KeyValSep => is_key_start = true,
+            TokenKind::InlineTableOpen | TokenKind::ArrayOpen => {
+                let container = containers.last().unwrap_or(&table);
+                // Directly assigned values are under their key, not their container
+                let path = if key.starts_with(container) {
+                    key.clone()
+                } else {
+                    container.clone()
+                };
+                containers.push(path);
+            }
+            TokenKind::InlineTableClose | TokenKind::ArrayClose => {
+                containers.pop();
+                key = containers.last().unwrap_or(&table).clone();
+            }
+            _ => {}
+        }
+    }
+    keys
+}
+
+#[cfg(test)]
+mod test {
+    use snapbox::IntoData;
+    use snapbox::assert_data_eq;
+    use snapbox::str;
+
+    #[track_caller]
+    fn valid(input: &str, expected: impl IntoData, expected_warnings: impl IntoData) {
+        let mut tokens = crate::toml::TomlTokens::parse(input);
+        let mut warnings = Vec::new();
+        super::rewrite_deprecated_keys(&mut tokens, &[], &mut warnings);
+        let actual = tokens.to_string();
+
+        assert_data_eq!(&actual, expected);
+        assert_data_eq!(warnings.join("\n"), expected_warnings);
+
+        let (_, errors) = toml::de::DeTable::parse_recoverable(&actual);
+        if !errors.is_empty() {
+            use std::fmt::Write as _;
+            let mut result = String::new();
+            writeln!(&mut result, "---").unwrap();
+            for error in errors {
+                writeln!(&mut result, "{error}").unwrap();
+                writeln!(&mut result, "---").unwrap();
+            }
+            panic!("failed to parse\n---\n{actual}\n{result}");
+        }
+    }
+
+    #[test]
+    fn empty() {
+        valid("", str![], str![]);
+    }
+
+    #[test]
+    fn headers() {
+        valid(
+            r#"
+[project]
+name = "foo"
+
+[project.metadata.docs]
+all-features = true
+
+[lib]
+crate_type = ["cdylib"]
+proc_macro = false
+
+[[example]]
+name = "ffi"
+crate_type = ["staticlib"]
+
+[dev_dependencies]
+snapbox = "0.6"
+
+[build_dependencies.cc]
+version = "1"
+default_features = false
+
+[target.'cfg(unix)'.dev_dependencies]
+libc = "0.2"
+"#,
+            str![[r#"
+
+[package]
+name = "foo"
+
+[package.metadata.docs]
+all-features = true
+
+[lib]
+crate-type = ["cdylib"]
+proc-macro = false
+
+[[example]]
+name = "ffi"
+crate-type = ["staticlib"]
+
+[dev-dependencies]
+snapbox = "0.6"
+
+[build-dependencies.cc]
+version = "1"
+default-features = false
+
+[target.'cfg(unix)'.dev-dependencies]
+libc = "0.2"
+
+"#]],
+            str![],
+        );
+    }
+
+    #[test]
+    fn dotted_keys_and_inline_tables() {
+        valid(
+            r#"
+project.name = "foo"
+dev_dependencies.snapbox = { version = "0.6", default_features = false }
+"lib" = { "proc_macro" = true }
+
+[dependencies]
+serde.version = "1"
+serde.default_features = false
+toml = { version = "1", default_features = false, features = ["serde"] }
+
+[target.'cfg(unix)']
+build_dependencies = { cc = { version = "1", default_features = false } }
+
+[package.metadata]
+default_features = false
+dev_dependencies = { default_features = false }
+"#,
+            str![[r#"
+
+project.name = "foo"
+dev-dependencies.snapbox = { version = "0.6", default-features = false }
+"lib" = { proc-macro = true }
+
+[dependencies]
+serde.version = "1"
+serde.default-features = false
+toml = { version = "1", default-features = false, features = ["serde"] }
+
+[target.'cfg(unix)']
+build-dependencies = { cc = { version = "1", default-features = false } }
+
+[package.metadata]
+default_features = false
+dev_dependencies = { default_features = false }
+
+"#]],
+            str!["both `project` and `package` are present, leaving `project` as is"],
+        );
+    }
+
+    #[test]
+    fn report_conflicts() {
+        valid(
+            r#"
+[package]
+name = "foo"
+
+[project]
+name = "foo"
+
+[dependencies]
+serde = { version = "1", default_features = false, default-features = false }
+toml = { version = "1", default_features = false }
+
+[dev-dependencies]
+a = "1"
+
+[dev_dependencies]
+b = "1"
+
+[dev_dependencies.c]
+version = "1"
+"#,
+            str![[r#"
+
+[package]
+name = "foo"
+
+[project]
+name = "foo"
+
+[dependencies]
+serde = { version = "1", default_features = false, default-features = false }
+toml = { version = "1", default-features = false }
+
+[dev-dependencies]
+a = "1"
+
+[dev_dependencies]
+b = "1"
+
+[dev_dependencies.c]
+version = "1"
+
+"#]],
+            str![[r#"
+both `project` and `package` are present, leaving `project` as is
+both `dependencies.serde.default_features` and `dependencies.serde.default-features` are present, leaving `default_features` as is
+both `dev_dependencies` and `dev-dependencies` are present, leaving `dev_dependencies` as is
+"#]],
+        );
+    }
+}
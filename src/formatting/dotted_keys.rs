@@ -0,0 +1,472 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use super::manifest::is_dependency_table;
+use super::skip_tables::SkipTables;
+use crate::config::options::DottedKeys;
+use crate::toml::DottedTable;
+use crate::toml::Entry;
+use crate::toml::Table;
+use crate::toml::TokenKind;
+use crate::toml::TomlToken;
+use crate::toml::TomlTokens;
+
+/// Rewrites the dependencies defined by dotted keys according to `policy`.
+///
+/// - [`DottedKeys::InlineTables`] merges `serde.version = "1"` and `serde.optional = true`
+///   into `serde = { version = "1", optional = true }`, in place of the first key
+/// - [`DottedKeys::StandardTables`] moves them into a `[dependencies.serde]` table after
+///   the table they were in
+///
+/// Only dotted keys in dependency tables are rewritten, so `edition.workspace = true` in
+/// `[package]` and `package.name = "foo"` before any header keep their spelling.  Tables
+/// are left alone when the rewrite would clash with a table header, or when their keys
+/// reach into `skip_tables`.  Inline tables are also not used for keys with comments
+/// or values spanning several lines, as inline tables must fit on one line.
+#[tracing::instrument]
+pub fn normalize_dotted_keys(
+    tokens: &mut TomlTokens<'_>,
+    policy: DottedKeys,
+    skip_tables: &[String],
+) {
+    let skip = SkipTables::new(skip_tables);
+    match policy {
+        DottedKeys::Preserve => {}
+        DottedKeys::InlineTables => expand_into_inline_tables(tokens, &skip),
+        // Each round peels one part off the keys, like `a.b.c` to `[a]` with `b.c`
+        DottedKeys::StandardTables => while expand_into_standard_tables(tokens, &skip) {},
+    }
+}
+
+fn expand_into_inline_tables(tokens: &mut TomlTokens<'_>, skip: &SkipTables) {
+    let tables = Table::new(tokens);
+    let mut edits = Vec::new();
+    for dotted in DottedTable::new(tokens, &tables) {
+        if !is_dependency(&dotted, &tables) || is_skipped(&dotted, &tables, skip) {
+            continue;
+        }
+        // Inline tables can't be extended by headers like `[a.b.c]`
+        let is_extended = tables.iter().any(|t| t.name().starts_with(dotted.name()));
+        let is_single_line = dotted.entries().iter().all(|e| {
+            tokens.tokens[e.span()]
+                .iter()
+                .filter(|t| t.kind == TokenKind::Newline)
+                .count()
+                <= 1
+                && !tokens.tokens[e.span()]
+                    .iter()
+                    .any(|t| t.kind == TokenKind::Comment)
+        });
+        if is_extended || !is_single_line {
+            continue;
+        }
+
+        let members = dotted
+            .entries()
+            .iter()
+            .map(|e| (raw_keys(tokens, e), raw(tokens, e.value_span())))
+            .collect::<Vec<_>>();
+        let (first, rest) = dotted.entries().split_first().expect("tables have entries");
+        let key = &members[0].0[0];
+        let members = members
+            .iter()
+            .map(|(keys, value)| (&keys[1..], value.as_str()))
+            .collect();
+        let text = format!("{key} = {}", inline_table(members));
+        edits.push((
+            first.key_span().start..first.value_span().end,
+            TomlTokens::parse_snippet(&text),
+        ));
+        edits.extend(rest.iter().map(|e| (e.span(), Vec::new())));
+    }
+    apply(tokens, edits);
+}
+
+/// Renders `members`, by their remaining keys, as an inline table
+fn inline_table(members: Vec<(&[String], &str)>) -> String {
+    let mut groups = Vec::<(&String, Vec<(&[String], &str)>)>::new();
+    for (keys, value) in members {
+        let Some((key, rest)) = keys.split_first() else {
+            continue;
+        };
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, group)) => group.push((rest, value)),
+            None => groups.push((key, vec![(rest, value)])),
+        }
+    }
+
+    let members = groups
+        .into_iter()
+        .map(|(key, group)| match group.as_slice() {
+            [([], value)] => format!("{key} = {value}"),
+            _ => format!("{key} = {}", inline_table(group)),
+        })
+        .collect::<Vec<_>>();
+    format!("{{ {} }}", members.join(", "))
+}
+
+/// Moves the first part of dotted keys into table headers, returning whether any moved
+fn expand_into_standard_tables(tokens: &mut TomlTokens<'_>, skip: &SkipTables) -> bool {
+    let tables = Table::new(tokens);
+    let headers = tables
+        .iter()
+        .filter(|t| !t.is_array_table())
+        .map(|t| t.name())
+        .collect::<HashSet<_>>();
+    let root_end = tables
+        .first()
+        .map(|t| t.span().start)
+        .unwrap_or(tokens.len());
+
+    let mut removed = Vec::new();
+    let mut inserts = Vec::<(usize, Vec<String>)>::new();
+    for dotted in DottedTable::new(tokens, &tables) {
+        if headers.contains(dotted.name())
+            || !is_dependency(&dotted, &tables)
+            || is_skipped(&dotted, &tables, skip)
+        {
+            continue;
+        }
+
+        let (at, parent) = match dotted.parent() {
+            Some(p) => (tables[p].body_span().end, header_keys(tokens, &tables[p])),
+            None => (root_end, Vec::new()),
+        };
+        let key = raw_keys(tokens, &dotted.entries()[0]).swap_remove(0);
+        let mut text = format!("[{}]\n", [parent, vec![key]].concat().join("."));
+        for entry in dotted.entries() {
+            removed.push(entry.span());
+            text.push_str(&without_first_key(tokens, entry));
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+        }
+        match inserts.last_mut() {
+            Some((last, texts)) if *last == at => texts.push(text),
+            _ => inserts.push((at, vec![text])),
+        }
+    }
+    if removed.is_empty() {
+        return false;
+    }
+
+    let mut edits = removed
+        .iter()
+        .map(|span| (span.clone(), Vec::new()))
+        .collect::<Vec<_>>();
+    for (at, texts) in inserts {
+        let is_before_table = at < tokens.len();
+        let (at, has_blank_line) = insertion_point(tokens, at, &removed);
+        let separator = match at.checked_sub(1).map(|i| tokens.tokens[i].kind) {
+            None => "",
+            Some(TokenKind::Newline) => "\n",
+            Some(_) => "\n\n",
+        };
+        let mut text = format!("{separator}{}", texts.join("\n"));
+        if is_before_table && !has_blank_line {
+            text.push('\n');
+        }
+        edits.push((at..at, TomlTokens::parse_snippet(&text)));
+    }
+    apply(tokens, edits);
+    true
+}
+
+/// Whether `dotted` is a dependency, like `serde` in `[dependencies]`
+fn is_dependency(dotted: &DottedTable, tables: &[Table]) -> bool {
+    dotted
+        .parent()
+        .map(|p| is_dependency_table(tables[p].name()))
+        .unwrap_or(false)
+}
+
+/// Whether any key of `dotted` is in or holds a skipped table
+fn is_skipped(dotted: &DottedTable, tables: &[Table], skip: &SkipTables) -> bool {
+    let parent = dotted
+        .parent()
+        .map(|p| tables[p].name())
+        .unwrap_or_default();
+    dotted
+        .entries()
+        .iter()
+        .any(|e| skip.overlaps(&[parent, e.key()].concat()))
+}
+
+/// Where to insert tables meant for `at`, right after the last line left once `removed` is
+/// gone, and whether blank lines remain between there and `at`
+fn insertion_point(tokens: &TomlTokens<'_>, at: usize, removed: &[Range<usize>]) -> (usize, bool) {
+    let is_kept = |i: usize| !removed.iter().any(|r| r.contains(&i));
+    let is_blank = |i: usize| {
+        matches!(
+            tokens.tokens[i].kind,
+            TokenKind::Newline | TokenKind::Whitespace
+        )
+    };
+    let start = (0..at)
+        .rev()
+        .find(|&i| is_kept(i) && !is_blank(i))
+        .map(|last| {
+            (last..at)
+                .find(|&i| tokens.tokens[i].kind == TokenKind::Newline)
+                .map(|i| i + 1)
+                .unwrap_or(at)
+        })
+        .unwrap_or(0);
+    let has_blank_line =
+        (start..at).any(|i| is_kept(i) && tokens.tokens[i].kind == TokenKind::Newline);
+    (start, has_blank_line)
+}
+
+/// The raw text of `entry` with the first part of its key removed
+fn without_first_key(tokens: &TomlTokens<'_>, entry: &Entry) -> String {
+    let key = entry.key_span();
+    let second = (key.start + 1..key.end)
+        .find(|&i| tokens.tokens[i].kind == TokenKind::SimpleKey)
+        .expect("dotted keys have several parts");
+    let span = entry.span();
+    let mut text = raw(tokens, span.start..key.start);
+    text.push_str(&raw(tokens, second..span.end));
+    text
+}
+
+fn raw_keys(tokens: &TomlTokens<'_>, entry: &Entry) -> Vec<String> {
+    tokens.tokens[entry.key_span()]
+        .iter()
+        .filter(|t| t.kind == TokenKind::SimpleKey)
+        .map(|t| t.raw.to_string())
+        .collect()
+}
+
+fn header_keys(tokens: &TomlTokens<'_>, table: &Table) -> Vec<String> {
+    tokens.tokens[table.header_span()]
+        .iter()
+        .filter(|t| t.kind == TokenKind::SimpleKey)
+        .map(|t| t.raw.to_string())
+        .collect()
+}
+
+fn raw(tokens: &TomlTokens<'_>, span: Range<usize>) -> String {
+    tokens.tokens[span].iter().map(|t| t.raw.as_ref()).collect()
+}
+
+/// Replaces each span with its tokens, working back to front to keep spans valid
+fn apply(tokens: &mut TomlTokens<'_>, mut edits: Vec<(Range<usize>, Vec<TomlToken<'static>>)>) {
+    edits.sort_by_key(|(span, _)| (span.start, span.end));
+    for (span, replacement) in edits.into_iter().rev() {
+        tokens.tokens.splice(span, replacement);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use snapbox::IntoData;
+    use snapbox::assert_data_eq;
+    use snapbox::str;
+
+    use crate::config::options::DottedKeys;
+
+    #[track_caller]
+    fn valid(input: &str, policy: DottedKeys, expected: impl IntoData) {
+        let mut tokens = crate::toml::TomlTokens::parse(input);
+        super::normalize_dotted_keys(&mut tokens, policy, &[]);
+        let actual = tokens.to_string();
+
+        assert_data_eq!(&actual, expected);
+
+        let (_, errors) = toml::de::DeTable::parse_recoverable(&actual);
+        if !errors.is_empty() {
+            use std::fmt::Write as _;
+            let mut result = String::new();
+            writeln!(&mut result, "---").unwrap();
+            for error in errors {
+                writeln!(&mut result, "{error}").unwrap();
+                writeln!(&mut result, "---").unwrap();
+            }
+            panic!("failed to parse\n---\n{actual}\n{result}");
+        }
+    }
+
+    const INPUT: &str = r#"package.name = "foo"
+package.version = "0.1.0"
+
+[dependencies]
+serde.version = "1"
+toml = "0.9"
+serde.features = ["derive"]
+tracing.workspace = true # shared
+
+[lints.rust]
+unsafe_code.level = "forbid"
+unsafe_code.priority.value = -1
+"#;
+
+    #[test]
+    fn empty() {
+        valid("", DottedKeys::StandardTables, str![]);
+    }
+
+    #[test]
+    fn preserve() {
+        valid(
+            INPUT,
+            DottedKeys::Preserve,
+            str![[r#"
+package.name = "foo"
+package.version = "0.1.0"
+
+[dependencies]
+serde.version = "1"
+toml = "0.9"
+serde.features = ["derive"]
+tracing.workspace = true # shared
+
+[lints.rust]
+unsafe_code.level = "forbid"
+unsafe_code.priority.value = -1
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn inline_tables() {
+        valid(
+            INPUT,
+            DottedKeys::InlineTables,
+            str![[r#"
+package.name = "foo"
+package.version = "0.1.0"
+
+[dependencies]
+serde = { version = "1", features = ["derive"] }
+toml = "0.9"
+tracing.workspace = true # shared
+
+[lints.rust]
+unsafe_code.level = "forbid"
+unsafe_code.priority.value = -1
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn standard_tables() {
+        valid(
+            INPUT,
+            DottedKeys::StandardTables,
+            str![[r#"
+package.name = "foo"
+package.version = "0.1.0"
+
+[dependencies]
+toml = "0.9"
+
+[dependencies.serde]
+version = "1"
+features = ["derive"]
+
+[dependencies.tracing]
+workspace = true # shared
+
+[lints.rust]
+unsafe_code.level = "forbid"
+unsafe_code.priority.value = -1
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_tables_extended_by_headers() {
+        valid(
+            r#"
+[dependencies]
+serde.version = "1"
+
+[dependencies.serde.metadata]
+x = 1
+"#,
+            DottedKeys::InlineTables,
+            str![[r#"
+
+[dependencies]
+serde.version = "1"
+
+[dependencies.serde.metadata]
+x = 1
+
+"#]],
+        );
+        valid(
+            r#"
+[dependencies]
+serde.version = "1"
+
+[dependencies.serde.metadata]
+x = 1
+"#,
+            DottedKeys::StandardTables,
+            str![[r#"
+
+[dependencies]
+
+[dependencies.serde]
+version = "1"
+
+[dependencies.serde.metadata]
+x = 1
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn other_tables() {
+        const OTHER_TABLES: &str = r#"[package]
+name = "foo"
+edition.workspace = true
+
+[[bin]]
+name = "a"
+metadata.x = 1
+
+[target.'cfg(unix)']
+dependencies.libc = "0.2"
+"#;
+        valid(
+            OTHER_TABLES,
+            DottedKeys::InlineTables,
+            str![[r#"
+[package]
+name = "foo"
+edition.workspace = true
+
+[[bin]]
+name = "a"
+metadata.x = 1
+
+[target.'cfg(unix)']
+dependencies.libc = "0.2"
+
+"#]],
+        );
+        valid(
+            OTHER_TABLES,
+            DottedKeys::StandardTables,
+            str![[r#"
+[package]
+name = "foo"
+edition.workspace = true
+
+[[bin]]
+name = "a"
+metadata.x = 1
+
+[target.'cfg(unix)']
+dependencies.libc = "0.2"
+
+"#]],
+        );
+    }
+}
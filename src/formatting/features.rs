@@ -0,0 +1,281 @@
+use crate::toml::Array;
+use crate::toml::Entry;
+use crate::toml::Table;
+use crate::toml::TokenKind;
+use crate::toml::TomlToken;
+use crate::toml::TomlTokens;
+
+/// Normalizes the `[features]` table.
+///
+/// - `default` goes first, and with `sort_keys` the other features are sorted by name
+/// - Each feature's values are sorted and deduplicated, with plain features first, then
+///   `dep:` entries, then `crate/feature` entries
+///
+/// Comments directly above a feature or value move with it.  Duplicate values with
+/// comments are kept so no comment is lost.  Blank lines split the table into groups
+/// whose features are sorted independently.
+#[tracing::instrument]
+pub fn normalize_features(tokens: &mut TomlTokens<'_>, sort_keys: bool) {
+    let Some(body) = features_body(tokens) else {
+        return;
+    };
+
+    let entries = Entry::parse(tokens, body);
+    // Back to front so earlier spans stay valid
+    for entry in entries.iter().rev() {
+        let open = entry.value_span().start;
+        if tokens.tokens.get(open).map(|t| t.kind) == Some(TokenKind::ArrayOpen) {
+            sort_values(tokens, open);
+        }
+    }
+
+    let Some(body) = features_body(tokens) else {
+        return;
+    };
+    let entries = Entry::parse(tokens, body.clone());
+    let runs = Entry::runs(&entries);
+    for run in runs.iter().rev() {
+        Entry::sort_by(tokens, run, |a, b| {
+            let a = (!is_default(a), sort_keys.then(|| a.key()));
+            let b = (!is_default(b), sort_keys.then(|| b.key()));
+            a.cmp(&b)
+        });
+    }
+
+    // `default` is first within its group but may still be below other groups
+    let entries = Entry::parse(tokens, body);
+    if let Some(default) = entries.iter().skip(1).find(|e| is_default(e)) {
+        let mut moved = tokens.tokens.drain(default.span()).collect::<Vec<_>>();
+        if moved.last().map(|t| t.kind) != Some(TokenKind::Newline) {
+            moved.push(TomlToken::NL);
+        }
+        let at = entries[0].span().start;
+        tokens.tokens.splice(at..at, moved);
+    }
+}
+
+fn features_body(tokens: &TomlTokens<'_>) -> Option<std::ops::Range<usize>> {
+    Table::new(tokens)
+        .into_iter()
+        .find(|t| !t.is_array_table() && t.name() == ["features"])
+        .map(|t| t.body_span())
+}
+
+fn is_default(entry: &Entry) -> bool {
+    entry.key() == ["default"]
+}
+
+fn sort_values(tokens: &mut TomlTokens<'_>, open: usize) {
+    let Some(array) = Array::parse(tokens, open) else {
+        return;
+    };
+    let values = array
+        .elements()
+        .iter()
+        .map(|e| tokens.tokens[e.value()].decoded.as_deref())
+        .collect::<Option<Vec<_>>>();
+    let Some(values) = values else {
+        return;
+    };
+
+    let mut order = (0..values.len()).collect::<Vec<_>>();
+    order.sort_by_key(|i| (value_group(values[*i]), values[*i]));
+    let mut seen = std::collections::HashSet::new();
+    order.retain(|i| seen.insert(values[*i]) || array.elements()[*i].has_comments());
+    if order.iter().copied().eq(0..values.len()) {
+        return;
+    }
+
+    array.rewrite(tokens, &order);
+}
+
+/// Plain features, then `dep:` entries, then `crate/feature` entries
+fn value_group(value: &str) -> u8 {
+    if value.starts_with("dep:") {
+        1
+    } else if value.contains('/') {
+        2
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use snapbox::IntoData;
+    use snapbox::assert_data_eq;
+    use snapbox::str;
+
+    #[track_caller]
+    fn valid(input: &str, sort_keys: bool, expected: impl IntoData) {
+        let mut tokens = crate::toml::TomlTokens::parse(input);
+        super::normalize_features(&mut tokens, sort_keys);
+        let actual = tokens.to_string();
+
+        assert_data_eq!(&actual, expected);
+
+        let (_, errors) = toml::de::DeTable::parse_recoverable(&actual);
+        if !errors.is_empty() {
+            use std::fmt::Write as _;
+            let mut result = String::new();
+            writeln!(&mut result, "---").unwrap();
+            for error in errors {
+                writeln!(&mut result, "{error}").unwrap();
+                writeln!(&mut result, "---").unwrap();
+            }
+            panic!("failed to parse\n---\n{actual}\n{result}");
+        }
+    }
+
+    #[test]
+    fn empty() {
+        valid("", true, str![]);
+    }
+
+    #[test]
+    fn default_first() {
+        valid(
+            r#"
+[features]
+std = []
+# Enabled unless opted out
+default = ["std"]
+alloc = []
+"#,
+            false,
+            str![[r#"
+
+[features]
+# Enabled unless opted out
+default = ["std"]
+std = []
+alloc = []
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn default_first_across_groups() {
+        valid(
+            r#"
+[features]
+std = []
+
+default = ["std"]
+"#,
+            false,
+            str![[r#"
+
+[features]
+default = ["std"]
+std = []
+
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn sort_keys() {
+        valid(
+            r#"
+[features]
+std = []
+default = ["std"]
+alloc = []
+
+unstable = []
+experimental = []
+"#,
+            true,
+            str![[r#"
+
+[features]
+default = ["std"]
+alloc = []
+std = []
+
+experimental = []
+unstable = []
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn sort_and_dedup_values() {
+        valid(
+            r#"
+[features]
+default = ["std", "serde?/std", "dep:serde", "alloc", "std"]
+full = [
+    "serde/derive",
+    # Needed everywhere
+    "std", # really
+    "dep:toml",
+    "alloc",
+    "std",
+]
+"#,
+            false,
+            str![[r#"
+
+[features]
+default = ["alloc", "std", "dep:serde", "serde?/std"]
+full = [
+    "alloc",
+    # Needed everywhere
+    "std", # really
+    "dep:toml",
+    "serde/derive",
+]
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_commented_duplicates() {
+        valid(
+            r#"
+[features]
+std = [
+    "b",
+    "a", # first
+    "a", # second
+]
+"#,
+            false,
+            str![[r#"
+
+[features]
+std = [
+    "a", # first
+    "a", # second
+    "b",
+]
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_other_tables() {
+        valid(
+            r#"
+[package]
+default = ["b", "a"]
+std = []
+"#,
+            true,
+            str![[r#"
+
+[package]
+default = ["b", "a"]
+std = []
+
+"#]],
+        );
+    }
+}
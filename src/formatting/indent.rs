@@ -109,7 +109,8 @@ fn close_count(tokens: &crate::toml::TomlTokens<'_>, i: usize) -> usize {
             )
         })
         .count();
-    let end = i + token_line_count + 1;
+    // Past the last token when the file doesn't end with a newline
+    let end = (i + token_line_count + 1).min(tokens.len());
     tokens.tokens[i..end]
         .iter()
         .filter(|t| matches!(t.kind, TokenKind::ArrayClose | TokenKind::InlineTableClose))
@@ -289,6 +290,20 @@ f = 10
 
 g = 11
 
+"#]],
+        );
+    }
+
+    #[test]
+    fn no_trailing_newline() {
+        valid(
+            "a = [\n1,\n]",
+            false,
+            4,
+            str![[r#"
+a = [
+    1,
+]
 "#]],
         );
     }
@@ -0,0 +1,268 @@
+use spdx::lexer::Lexer;
+use spdx::lexer::Token;
+
+use crate::toml::DottedTable;
+use crate::toml::Entry;
+use crate::toml::Table;
+use crate::toml::TokenKind;
+use crate::toml::TomlTokens;
+
+/// Accepts what crates in the wild use, without guessing at imprecise license names
+const PARSE_MODE: spdx::ParseMode = spdx::ParseMode {
+    allow_lower_case_operators: true,
+    allow_slash_as_or_operator: true,
+    allow_imprecise_license_names: false,
+    allow_postfix_plus_on_gpl: true,
+};
+
+/// Normalizes `license` in `[package]` and `[workspace.package]` to SPDX 2 syntax.
+///
+/// - `/` becomes `OR`
+/// - Operators are uppercase, with a single space around them
+/// - Known license and exception identifiers use their canonical casing, like `mit` to `MIT`
+///
+/// This includes dotted keys, like `package.license` before any header.  Licenses that
+/// don't parse are left alone and reported in `warnings`.
+#[tracing::instrument(skip(warnings))]
+pub fn normalize_licenses(tokens: &mut TomlTokens<'_>, warnings: &mut Vec<String>) {
+    let tables = Table::new(tokens);
+    let mut keys = Vec::new();
+    for table in &tables {
+        if table.is_array_table() {
+            continue;
+        }
+        for entry in Entry::parse(tokens, table.body_span()) {
+            keys.push(([table.name(), entry.key()].concat(), entry.value_span()));
+        }
+    }
+    for dotted in DottedTable::new(tokens, &tables) {
+        if dotted.parent().is_none() {
+            keys.extend(
+                dotted
+                    .entries()
+                    .iter()
+                    .map(|e| (e.key().to_vec(), e.value_span())),
+            );
+        }
+    }
+
+    for (path, value) in keys {
+        let is_license = path == ["package", "license"]
+            || path == ["project", "license"]
+            || path == ["workspace", "package", "license"];
+        if !is_license {
+            continue;
+        }
+        let token = &mut tokens.tokens[value.start];
+        if token.kind != TokenKind::Scalar {
+            continue;
+        }
+        let Some(license) = token.decoded.as_deref() else {
+            continue;
+        };
+        match normalized(license) {
+            Ok(normalized) if normalized != license => token.set_string(&normalized),
+            Ok(_) => {}
+            Err(reason) => warnings.push(format!(
+                "`{}` is not a valid SPDX expression ({reason}), leaving it as is",
+                path.join(".")
+            )),
+        }
+    }
+}
+
+fn normalized(license: &str) -> Result<String, spdx::error::Reason> {
+    let license = canonical_case(license);
+    spdx::Expression::parse_mode(&license, PARSE_MODE).map_err(|e| e.reason)?;
+
+    let mut normalized = String::new();
+    for token in Lexer::new_mode(&license, PARSE_MODE) {
+        match token.map_err(|e| e.reason)?.token {
+            Token::Spdx(id) => normalized.push_str(id.name),
+            Token::LicenseRef { doc_ref, lic_ref } => {
+                if let Some(doc_ref) = doc_ref {
+                    normalized.push_str(&format!("DocumentRef-{doc_ref}:"));
+                }
+                normalized.push_str(&format!("LicenseRef-{lic_ref}"));
+            }
+            Token::Exception(id) => normalized.push_str(id.name),
+            Token::Plus => normalized.push('+'),
+            Token::OpenParen => normalized.push('('),
+            Token::CloseParen => normalized.push(')'),
+            Token::With => normalized.push_str(" WITH "),
+            Token::And => normalized.push_str(" AND "),
+            Token::Or => normalized.push_str(" OR "),
+        }
+    }
+    Ok(normalized)
+}
+
+/// Fixes the case of operators and identifiers, which SPDX matches case-insensitively
+fn canonical_case(license: &str) -> String {
+    let is_term_char = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == ':';
+    let mut cased = String::new();
+    let mut rest = license;
+    while !rest.is_empty() {
+        let end = rest.find(|c| !is_term_char(c)).unwrap_or(rest.len());
+        let (term, after) = rest.split_at(end);
+        cased.push_str(canonical_term(term).unwrap_or(term));
+        let end = after.find(is_term_char).unwrap_or(after.len());
+        let (separator, after) = after.split_at(end);
+        cased.push_str(separator);
+        rest = after;
+    }
+    cased
+}
+
+fn canonical_term(term: &str) -> Option<&'static str> {
+    ["AND", "OR", "WITH"]
+        .into_iter()
+        .find(|op| op.eq_ignore_ascii_case(term))
+        .or_else(|| {
+            spdx::identifiers::LICENSES
+                .iter()
+                .map(|(id, _, _)| *id)
+                .chain(spdx::identifiers::EXCEPTIONS.iter().map(|(id, _)| *id))
+                .find(|id| id.eq_ignore_ascii_case(term))
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use snapbox::IntoData;
+    use snapbox::assert_data_eq;
+    use snapbox::str;
+
+    #[track_caller]
+    fn valid(input: &str, expected: impl IntoData, expected_warnings: impl IntoData) {
+        let mut tokens = crate::toml::TomlTokens::parse(input);
+        let mut warnings = Vec::new();
+        super::normalize_licenses(&mut tokens, &mut warnings);
+        let actual = tokens.to_string();
+
+        assert_data_eq!(&actual, expected);
+        assert_data_eq!(warnings.join("\n"), expected_warnings);
+
+        let (_, errors) = toml::de::DeTable::parse_recoverable(&actual);
+        if !errors.is_empty() {
+            use std::fmt::Write as _;
+            let mut result = String::new();
+            writeln!(&mut result, "---").unwrap();
+            for error in errors {
+                writeln!(&mut result, "{error}").unwrap();
+                writeln!(&mut result, "---").unwrap();
+            }
+            panic!("failed to parse\n---\n{actual}\n{result}");
+        }
+    }
+
+    #[test]
+    fn empty() {
+        valid("", str![], str![]);
+    }
+
+    #[test]
+    fn package() {
+        valid(
+            r#"
+[package]
+license = "MIT/Apache-2.0"
+"#,
+            str![[r#"
+
+[package]
+license = "MIT OR Apache-2.0"
+
+"#]],
+            str![],
+        );
+    }
+
+    #[test]
+    fn expressions() {
+        valid(
+            r#"
+[workspace.package]
+license = "mit or  apache-2.0"
+
+[package]
+license = '(mit/Apache-2.0)and  Zlib'
+
+[project]
+license = "apache-2.0 with llvm-exception / GPL-2.0+ OR LicenseRef-Custom"
+"#,
+            str![[r#"
+
+[workspace.package]
+license = "MIT OR Apache-2.0"
+
+[package]
+license = '(MIT OR Apache-2.0) AND Zlib'
+
+[project]
+license = "Apache-2.0 WITH LLVM-exception OR GPL-2.0+ OR LicenseRef-Custom"
+
+"#]],
+            str![],
+        );
+    }
+
+    #[test]
+    fn dotted_keys() {
+        valid(
+            r#"package.license = "MIT/Apache-2.0"
+
+[workspace]
+package.license = "mit or apache-2.0"
+package.metadata.license = "mit/apache-2.0"
+"#,
+            str![[r#"
+package.license = "MIT OR Apache-2.0"
+
+[workspace]
+package.license = "MIT OR Apache-2.0"
+package.metadata.license = "mit/apache-2.0"
+
+"#]],
+            str![],
+        );
+    }
+
+    #[test]
+    fn keep_unparseable_and_other_values() {
+        valid(
+            r#"
+[package]
+license = "MIT OR"
+
+[workspace.package]
+license = "GNU GPL v2"
+
+[package.metadata]
+license = "mit/apache-2.0"
+
+[dependencies]
+license = "1"
+"#,
+            str![[r#"
+
+[package]
+license = "MIT OR"
+
+[workspace.package]
+license = "GNU GPL v2"
+
+[package.metadata]
+license = "mit/apache-2.0"
+
+[dependencies]
+license = "1"
+
+"#]],
+            str![[r#"
+`package.license` is not a valid SPDX expression (expected one of `<license>`, `(` here), leaving it as is
+`workspace.package.license` is not a valid SPDX expression (unknown term), leaving it as is
+"#]],
+        );
+    }
+}
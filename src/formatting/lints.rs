@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use crate::toml::Entry;
+use crate::toml::Table;
+use crate::toml::TokenKind;
+use crate::toml::TomlTokens;
+
+/// Sorts the lints of `[lints.<tool>]` and `[workspace.lints.<tool>]` tables.
+///
+/// Lints are grouped by `priority`, lowest first, and then sorted by name.  This
+/// matches the order Cargo passes them to the compiler, so precedence is unchanged.
+///
+/// Comments directly above or after a lint move with it.  Blank lines split the table
+/// into groups that are sorted independently.  Groups with a `priority` that isn't a
+/// plain integer are left alone.
+#[tracing::instrument]
+pub fn sort_lints(tokens: &mut TomlTokens<'_>) {
+    let tables = Table::new(tokens);
+    for table in tables.iter().rev() {
+        if table.is_array_table() || !is_lints_table(table.name()) {
+            continue;
+        }
+
+        let entries = Entry::parse(tokens, table.body_span());
+        for run in Entry::runs(&entries).into_iter().rev() {
+            let Some(priorities) = priorities(tokens, run) else {
+                continue;
+            };
+            let rank = |entry: &Entry| {
+                let name = &entry.key()[0];
+                (priorities.get(name).copied().unwrap_or(0), name.clone())
+            };
+            Entry::sort_by(tokens, run, |a, b| rank(a).cmp(&rank(b)));
+        }
+    }
+}
+
+fn is_lints_table(name: &[String]) -> bool {
+    match name {
+        [lints, _] => lints == "lints",
+        [workspace, lints, _] => workspace == "workspace" && lints == "lints",
+        _ => false,
+    }
+}
+
+/// The explicit priority of each lint, whether from `lint = { priority = 1 }` or
+/// `lint.priority = 1`
+fn priorities(tokens: &TomlTokens<'_>, entries: &[Entry]) -> Option<HashMap<String, i64>> {
+    let mut priorities = HashMap::new();
+    for entry in entries {
+        let name = &entry.key()[0];
+        let value = match entry.key() {
+            [_] if tokens.tokens[entry.value_span().start].kind == TokenKind::InlineTableOpen => {
+                Entry::parse_inline(tokens, entry.value_span().start)
+                    .into_iter()
+                    .find(|e| e.key() == ["priority"])
+                    .map(|e| e.value_span())
+            }
+            [_, priority] if priority == "priority" => Some(entry.value_span()),
+            _ => None,
+        };
+        if let Some(value) = value {
+            let raw = tokens.tokens[value.start].raw.replace('_', "");
+            priorities.insert(name.clone(), raw.parse::<i64>().ok()?);
+        }
+    }
+    Some(priorities)
+}
+
+#[cfg(test)]
+mod test {
+    use snapbox::IntoData;
+    use snapbox::assert_data_eq;
+    use snapbox::str;
+
+    #[track_caller]
+    fn valid(input: &str, expected: impl IntoData) {
+        let mut tokens = crate::toml::TomlTokens::parse(input);
+        super::sort_lints(&mut tokens);
+        let actual = tokens.to_string();
+
+        assert_data_eq!(&actual, expected);
+
+        let (_, errors) = toml::de::DeTable::parse_recoverable(&actual);
+        if !errors.is_empty() {
+            use std::fmt::Write as _;
+            let mut result = String::new();
+            writeln!(&mut result, "---").unwrap();
+            for error in errors {
+                writeln!(&mut result, "{error}").unwrap();
+                writeln!(&mut result, "---").unwrap();
+            }
+            panic!("failed to parse\n---\n{actual}\n{result}");
+        }
+    }
+
+    #[test]
+    fn empty() {
+        valid("", str![]);
+    }
+
+    #[test]
+    fn sort_by_name() {
+        valid(
+            r#"
+[workspace.lints.clippy]
+let_and_return = "allow"  # sometimes good to name what you are returning
+bool_assert_comparison = "allow"
+# Often clearer
+needless_return = "allow"
+dbg_macro = "warn"
+
+[workspace.lints.rust]
+unsafe_code = "forbid"
+missing_docs = "warn"
+"#,
+            str![[r#"
+
+[workspace.lints.clippy]
+bool_assert_comparison = "allow"
+dbg_macro = "warn"
+let_and_return = "allow"  # sometimes good to name what you are returning
+# Often clearer
+needless_return = "allow"
+
+[workspace.lints.rust]
+missing_docs = "warn"
+unsafe_code = "forbid"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn group_by_priority() {
+        valid(
+            r#"
+[lints.clippy]
+pedantic = { level = "warn", priority = -1 }
+module_name_repetitions = "allow"
+all = { priority = -2, level = "warn" }
+cargo.level = "warn"
+cargo.priority = -1
+bool_assert_comparison = "allow"
+restriction = { level = "allow", priority = 1_0 }
+"#,
+            str![[r#"
+
+[lints.clippy]
+all = { priority = -2, level = "warn" }
+cargo.level = "warn"
+cargo.priority = -1
+pedantic = { level = "warn", priority = -1 }
+bool_assert_comparison = "allow"
+module_name_repetitions = "allow"
+restriction = { level = "allow", priority = 1_0 }
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_unknown_priority() {
+        valid(
+            r#"
+[lints.rust]
+b = { level = "warn", priority = "high" }
+a = "warn"
+"#,
+            str![[r#"
+
+[lints.rust]
+b = { level = "warn", priority = "high" }
+a = "warn"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_other_tables() {
+        valid(
+            r#"
+[lints]
+workspace = true
+
+[workspace.metadata.lints.rust]
+b = "warn"
+a = "warn"
+"#,
+            str![[r#"
+
+[lints]
+workspace = true
+
+[workspace.metadata.lints.rust]
+b = "warn"
+a = "warn"
+
+"#]],
+        );
+    }
+}
@@ -0,0 +1,46 @@
+//! Knowledge of `Cargo.toml`'s schema shared between passes
+
+/// Whether `name` is a table of dependencies, like `[dependencies]` or
+/// `[target.'cfg(unix)'.dev-dependencies]`
+pub(crate) fn is_dependency_table(name: &[String]) -> bool {
+    match name {
+        [kind] => is_dependency_kind(kind),
+        [workspace, kind] => workspace == "workspace" && kind == "dependencies",
+        [target, _, kind] => target == "target" && is_dependency_kind(kind),
+        _ => false,
+    }
+}
+
+/// Whether `name` is a single dependency's table, like `[dependencies.serde]`
+pub(crate) fn is_dependency_subtable(name: &[String]) -> bool {
+    name.split_last()
+        .map(|(_, parent)| is_dependency_table(parent))
+        .unwrap_or(false)
+}
+
+fn is_dependency_kind(kind: &str) -> bool {
+    DEPENDENCY_KINDS.contains(&kind)
+}
+
+const DEPENDENCY_KINDS: [&str; 5] = [
+    "dependencies",
+    "dev-dependencies",
+    "build-dependencies",
+    "dev_dependencies",
+    "build_dependencies",
+];
+
+/// Position of `key` in a user-configured key `order`
+///
+/// Keys not in `order` rank with `*`, or after all listed keys without it.
+pub(crate) fn key_rank(order: &[String], key: &str) -> usize {
+    order.iter().position(|o| o == key).unwrap_or_else(|| {
+        order
+            .iter()
+            .position(|o| o == OTHER_KEYS)
+            .unwrap_or(order.len())
+    })
+}
+
+/// Placeholder in a key order for keys not otherwise listed
+const OTHER_KEYS: &str = "*";
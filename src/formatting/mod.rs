@@ -1,23 +1,63 @@
 mod blank_lines;
 mod datetime;
+mod dependency_keys;
+mod dependency_shorthand;
+mod dependency_tables;
+mod deprecated_keys;
+mod dotted_keys;
+mod features;
 mod generated;
 mod indent;
+mod licenses;
+mod lints;
+mod manifest;
 mod newline_style;
 mod overflow;
+mod package_keys;
+mod paths;
+mod redundant_defaults;
+mod skip_tables;
+mod sort_dependencies;
 mod space_separators;
 mod string;
+mod string_arrays;
+mod subtables;
+mod table_order;
+mod target_cfgs;
 mod trailing_comma;
 mod trailing_spaces;
 mod unused_parents;
+mod version_reqs;
+mod workspace_inheritance;
 
 pub use blank_lines::constrain_blank_lines;
 pub use datetime::normalize_datetime_separators;
+pub use dependency_keys::reorder_dependency_keys;
+pub use dependency_shorthand::collapse_version_only_dependencies;
+pub use dependency_tables::convert_dependency_tables;
+pub use deprecated_keys::rewrite_deprecated_keys;
+pub use dotted_keys::normalize_dotted_keys;
+pub use features::normalize_features;
 pub use generated::is_generated_file;
 pub use indent::normalize_indent;
+pub use licenses::normalize_licenses;
+pub use lints::sort_lints;
 pub use newline_style::apply_newline_style;
 pub use overflow::reflow_arrays;
+pub use package_keys::reorder_package_keys;
+pub use paths::normalize_paths;
+pub use redundant_defaults::remove_redundant_defaults;
+pub use skip_tables::restore_skipped_tables;
+pub use sort_dependencies::sort_dependencies;
 pub use space_separators::normalize_space_separators;
 pub use string::normalize_strings;
+pub use string_arrays::sort_string_arrays;
+pub use subtables::group_subtables;
+pub use table_order::reorder_tables;
+pub use target_cfgs::normalize_target_cfgs;
 pub use trailing_comma::adjust_trailing_comma;
 pub use trailing_spaces::trim_trailing_spaces;
 pub use unused_parents::remove_unused_parent_tables;
+pub use version_reqs::normalize_version_reqs;
+pub use workspace_inheritance::WorkspaceFields;
+pub use workspace_inheritance::inherit_workspace_fields;
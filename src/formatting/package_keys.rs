@@ -0,0 +1,216 @@
+use super::manifest::key_rank;
+use crate::toml::Entry;
+use crate::toml::Table;
+use crate::toml::TomlTokens;
+
+/// Sorts the keys of `[package]` and `[workspace.package]` into `order`.
+///
+/// Keys not in `order` take the place of `*`, or go last without it, keeping their
+/// relative order.  The order applies across the whole table, with blank lines staying
+/// where they are.
+#[tracing::instrument]
+pub fn reorder_package_keys(tokens: &mut TomlTokens<'_>, order: &[String]) {
+    let rank = |entry: &Entry| key_rank(order, &entry.key()[0]);
+
+    let tables = Table::new(tokens);
+    for table in tables.iter().rev() {
+        if !is_package_table(table.name()) {
+            continue;
+        }
+
+        let entries = Entry::parse(tokens, table.body_span());
+        Entry::sort_by(tokens, &entries, |a, b| rank(a).cmp(&rank(b)));
+    }
+}
+
+fn is_package_table(name: &[String]) -> bool {
+    match name {
+        [package] => package == "package" || package == "project",
+        [workspace, package] => workspace == "workspace" && package == "package",
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use snapbox::IntoData;
+    use snapbox::assert_data_eq;
+    use snapbox::str;
+
+    #[track_caller]
+    fn valid(input: &str, order: &[&str], expected: impl IntoData) {
+        let order = order.iter().map(|o| (*o).to_owned()).collect::<Vec<_>>();
+        let mut tokens = crate::toml::TomlTokens::parse(input);
+        super::reorder_package_keys(&mut tokens, &order);
+        let actual = tokens.to_string();
+
+        assert_data_eq!(&actual, expected);
+
+        let (_, errors) = toml::de::DeTable::parse_recoverable(&actual);
+        if !errors.is_empty() {
+            use std::fmt::Write as _;
+            let mut result = String::new();
+            writeln!(&mut result, "---").unwrap();
+            for error in errors {
+                writeln!(&mut result, "{error}").unwrap();
+                writeln!(&mut result, "---").unwrap();
+            }
+            panic!("failed to parse\n---\n{actual}\n{result}");
+        }
+    }
+
+    const DEFAULT: &[&str] = &["name", "version", "*", "description"];
+
+    #[test]
+    fn empty() {
+        valid("", DEFAULT, str![]);
+    }
+
+    #[test]
+    fn style_guide_order() {
+        valid(
+            r#"
+[package]
+description = "Cargo file formatter"
+edition = "2024"
+version = "0.1.0"
+license = "MIT"
+name = "foo"
+"#,
+            DEFAULT,
+            str![[r#"
+
+[package]
+name = "foo"
+version = "0.1.0"
+edition = "2024"
+license = "MIT"
+description = "Cargo file formatter"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn workspace_package() {
+        valid(
+            r#"
+[workspace.package]
+description = "shared"
+version = "0.1.0"
+
+[workspace.dependencies]
+version = "1"
+description = "1"
+"#,
+            DEFAULT,
+            str![[r#"
+
+[workspace.package]
+version = "0.1.0"
+description = "shared"
+
+[workspace.dependencies]
+version = "1"
+description = "1"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn comments_stay_attached() {
+        valid(
+            r#"
+[package]
+# What it does
+description = "foo" # keep short
+# Bump on release
+version = "0.1.0" # auto
+name = "foo"
+"#,
+            DEFAULT,
+            str![[r#"
+
+[package]
+name = "foo"
+# Bump on release
+version = "0.1.0" # auto
+# What it does
+description = "foo" # keep short
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn dotted_and_inherited_keys() {
+        valid(
+            r#"
+[package]
+edition.workspace = true
+version.workspace = true
+name = "foo"
+"#,
+            DEFAULT,
+            str![[r#"
+
+[package]
+name = "foo"
+version.workspace = true
+edition.workspace = true
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn order_across_groups() {
+        valid(
+            r#"
+[package]
+description = "foo"
+license = "MIT"
+
+# Bump on release
+version = "0.1.0"
+name = "foo"
+"#,
+            DEFAULT,
+            str![[r#"
+
+[package]
+name = "foo"
+# Bump on release
+version = "0.1.0"
+
+license = "MIT"
+description = "foo"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn without_placeholder() {
+        valid(
+            r#"
+[package]
+license = "MIT"
+edition = "2024"
+version = "0.1.0"
+name = "foo"
+"#,
+            &["name", "version"],
+            str![[r#"
+
+[package]
+name = "foo"
+version = "0.1.0"
+license = "MIT"
+edition = "2024"
+
+"#]],
+        );
+    }
+}
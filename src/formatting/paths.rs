@@ -0,0 +1,249 @@
+use super::manifest::is_dependency_subtable;
+use crate::toml::Array;
+use crate::toml::Entry;
+use crate::toml::Table;
+use crate::toml::TokenKind;
+use crate::toml::TomlTokens;
+
+/// Normalizes path values, like a dependency's `path` or `package.readme`.
+///
+/// - `\` becomes `/`
+/// - `./` and repeated or trailing `/` are removed
+///
+/// `..` is kept as removing it could change which file is referred to, and roots like
+/// `/` or `C:\` are left as is.
+#[tracing::instrument]
+pub fn normalize_paths(tokens: &mut TomlTokens<'_>) {
+    let tables = Table::new(tokens);
+    let root_end = tables
+        .first()
+        .map(|t| t.span().start)
+        .unwrap_or(tokens.len());
+    let bodies = std::iter::once((&[][..], 0..root_end))
+        .chain(tables.iter().map(|t| (t.name(), t.body_span())))
+        .collect::<Vec<_>>();
+
+    for (name, body) in bodies {
+        for entry in Entry::parse(tokens, body) {
+            let mut key = name.to_vec();
+            key.extend_from_slice(entry.key());
+            let value = entry.value_span().start;
+            match tokens.tokens[value].kind {
+                TokenKind::Scalar if is_path_key(&key) => normalize_path_value(tokens, value),
+                TokenKind::ArrayOpen if key == ["workspace", "members"] => {
+                    let Some(array) = Array::parse(tokens, value) else {
+                        continue;
+                    };
+                    for element in array.elements() {
+                        normalize_path_value(tokens, element.value());
+                    }
+                }
+                TokenKind::InlineTableOpen => {
+                    for inline in Entry::parse_inline(tokens, value) {
+                        let mut key = key.clone();
+                        key.extend_from_slice(inline.key());
+                        let value = inline.value_span().start;
+                        if tokens.tokens[value].kind == TokenKind::Scalar && is_path_key(&key) {
+                            normalize_path_value(tokens, value);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn is_path_key(key: &[String]) -> bool {
+    let Some((last, parent)) = key.split_last() else {
+        return false;
+    };
+    match (parent, last.as_str()) {
+        ([package], "build" | "readme" | "license-file") => {
+            package == "package" || package == "project"
+        }
+        ([workspace, package], "readme" | "license-file") => {
+            workspace == "workspace" && package == "package"
+        }
+        ([target], "path") => ["lib", "bin", "example", "test", "bench"].contains(&target.as_str()),
+        (dependency, "path") => is_dependency_subtable(dependency),
+        _ => false,
+    }
+}
+
+fn normalize_path_value(tokens: &mut TomlTokens<'_>, i: usize) {
+    let token = &mut tokens.tokens[i];
+    let Some(path) = token.decoded.as_deref() else {
+        return;
+    };
+    let Some(normalized) = normalized(path) else {
+        return;
+    };
+    if normalized != path {
+        token.set_string(&normalized);
+    }
+}
+
+fn normalized(path: &str) -> Option<String> {
+    // UNC paths and URLs have significant separators
+    if path.starts_with("//") || path.starts_with("\\\\") || path.contains("://") {
+        return None;
+    }
+
+    let is_absolute = path.starts_with(['/', '\\']);
+    let components = path
+        .split(['/', '\\'])
+        .filter(|c| !c.is_empty() && *c != ".")
+        .collect::<Vec<_>>();
+    // Roots like `/` and `C:\` would change meaning without their separator
+    let is_drive = |c: &str| {
+        let c = c.as_bytes();
+        c.len() == 2 && c[0].is_ascii_alphabetic() && c[1] == b':'
+    };
+    match components.as_slice() {
+        [] if is_absolute => return None,
+        [drive] if is_drive(drive) && drive.len() < path.len() => return None,
+        _ => {}
+    }
+    let normalized = match (is_absolute, components.is_empty()) {
+        (true, _) => format!("/{}", components.join("/")),
+        (false, true) => ".".to_owned(),
+        (false, false) => components.join("/"),
+    };
+    Some(normalized)
+}
+
+#[cfg(test)]
+mod test {
+    use snapbox::IntoData;
+    use snapbox::assert_data_eq;
+    use snapbox::str;
+
+    #[track_caller]
+    fn valid(input: &str, expected: impl IntoData) {
+        let mut tokens = crate::toml::TomlTokens::parse(input);
+        super::normalize_paths(&mut tokens);
+        let actual = tokens.to_string();
+
+        assert_data_eq!(&actual, expected);
+
+        let (_, errors) = toml::de::DeTable::parse_recoverable(&actual);
+        if !errors.is_empty() {
+            use std::fmt::Write as _;
+            let mut result = String::new();
+            writeln!(&mut result, "---").unwrap();
+            for error in errors {
+                writeln!(&mut result, "{error}").unwrap();
+                writeln!(&mut result, "---").unwrap();
+            }
+            panic!("failed to parse\n---\n{actual}\n{result}");
+        }
+    }
+
+    #[test]
+    fn empty() {
+        valid("", str![]);
+    }
+
+    #[test]
+    fn paths() {
+        valid(
+            r#"
+[package]
+build = "./build.rs"
+readme = '.\README.md'
+license-file = "../LICENSE"
+include = ["./src/"]
+
+[lib]
+path = "src//lib.rs"
+
+[[bin]]
+path = "./src/bin/main.rs"
+
+[dependencies]
+foo = { path = "./crates/foo/" }
+bar = { path = "crates\\bar", version = "1" }
+baz.path = "./."
+qux = "./1.0"
+
+[dependencies.quux]
+path = "/opt/quux/"
+
+[workspace]
+members = ["./crates/*/", "crates/foo"]
+exclude = ["./target/"]
+
+[workspace.package]
+readme = "./README.md"
+"#,
+            str![[r#"
+
+[package]
+build = "build.rs"
+readme = 'README.md'
+license-file = "../LICENSE"
+include = ["./src/"]
+
+[lib]
+path = "src/lib.rs"
+
+[[bin]]
+path = "src/bin/main.rs"
+
+[dependencies]
+foo = { path = "crates/foo" }
+bar = { path = "crates/bar", version = "1" }
+baz.path = "."
+qux = "./1.0"
+
+[dependencies.quux]
+path = "/opt/quux"
+
+[workspace]
+members = ["crates/*", "crates/foo"]
+exclude = ["./target/"]
+
+[workspace.package]
+readme = "README.md"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_significant_separators() {
+        valid(
+            r#"
+[package]
+build = false
+readme = "//server/share/README.md"
+
+[dependencies]
+foo = { path = "../foo" }
+root = { path = "/" }
+"#,
+            str![[r#"
+
+[package]
+build = false
+readme = "//server/share/README.md"
+
+[dependencies]
+foo = { path = "../foo" }
+root = { path = "/" }
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_roots() {
+        // Compared directly, as snapshots treat `\` as `/`
+        for root in ["/", "\\", "C:\\", "c:/", "\\\\server\\share\\"] {
+            assert_eq!(super::normalized(root), None, "{root}");
+        }
+        assert_eq!(super::normalized("C:\\deps\\"), Some("C:/deps".to_owned()));
+        assert_eq!(super::normalized("C:"), Some("C:".to_owned()));
+    }
+}
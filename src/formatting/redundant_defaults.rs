@@ -0,0 +1,423 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use super::manifest::is_dependency_subtable;
+use super::manifest::is_dependency_table;
+use super::skip_tables::SkipTables;
+use crate::toml::Entry;
+use crate::toml::Table;
+use crate::toml::TokenKind;
+use crate::toml::TomlTokens;
+
+/// Removes keys that restate Cargo's defaults.
+///
+/// - `publish = true` and `autobins = true`, and the like, in `[package]`
+/// - `default-features = true` in dependencies, unless inherited from the workspace
+/// - `path = "src/lib.rs"` in `[lib]`, unless `autolib = false`
+/// - `[[bin]]` tables that only name a binary Cargo would discover on its own
+///
+/// Keys are removed along with their trailing comments.  Tables left empty are
+/// removed too.  Tables in `skip_tables` are left alone.
+#[tracing::instrument]
+pub fn remove_redundant_defaults(tokens: &mut TomlTokens<'_>, skip_tables: &[String]) {
+    let skip = SkipTables::new(skip_tables);
+    let tables = Table::new(tokens);
+    let package = Package::new(tokens, &tables);
+
+    let mut removed_tables = HashSet::new();
+    let mut removals = Vec::new();
+    let mut had_entries = Vec::new();
+    for (i, table) in tables.iter().enumerate() {
+        let entries = Entry::parse(tokens, table.body_span());
+        had_entries.push(!entries.is_empty());
+        let name = table.name();
+        if skip.contains(name) {
+            continue;
+        }
+
+        if table.is_array_table() {
+            if name == ["bin"] && package.is_autodiscovered_bin(tokens, &entries) {
+                removed_tables.insert(i);
+            }
+            continue;
+        }
+        if is_dependency_table(name) {
+            removals.extend(dependency_defaults(tokens, &entries));
+            continue;
+        }
+
+        let is_dependency = is_dependency_subtable(name);
+        let is_redundant = |entry: &Entry| match (name, entry.key()) {
+            ([package], [key]) if package == "package" || package == "project" => {
+                const TRUE_KEYS: [&str; 6] = [
+                    "publish",
+                    "autolib",
+                    "autobins",
+                    "autoexamples",
+                    "autotests",
+                    "autobenches",
+                ];
+                TRUE_KEYS.contains(&key.as_str()) && is_true(tokens, entry)
+            }
+            ([lib], [path]) => {
+                // Without `autolib`, the path is what makes `src/lib.rs` a library
+                lib == "lib"
+                    && path == "path"
+                    && package.autolib
+                    && string(tokens, entry) == Some("src/lib.rs")
+            }
+            (_, [key]) => {
+                is_dependency
+                    && key == "default-features"
+                    && is_true(tokens, entry)
+                    && !entries.iter().any(|e| e.key() == ["workspace"])
+                    // An empty dependency table would lose the dependency entirely
+                    && 1 < entries.len()
+            }
+            _ => false,
+        };
+        removals.extend(
+            entries
+                .iter()
+                .filter(|e| is_redundant(e))
+                .map(|e| line_span(tokens, e)),
+        );
+    }
+
+    removals.sort_by_key(|r| r.start);
+    for removal in removals.into_iter().rev() {
+        tokens.tokens.drain(removal);
+    }
+
+    let tables = Table::new(tokens);
+    for (i, table) in tables.iter().enumerate().rev() {
+        let became_empty = had_entries[i]
+            && !is_dependency_subtable(table.name())
+            && Entry::parse(tokens, table.body_span()).is_empty();
+        if removed_tables.contains(&i) || became_empty {
+            tokens
+                .tokens
+                .drain(table.span().start..table.body_span().end);
+        }
+    }
+}
+
+/// Spans of `default-features = true` in the inline tables and dotted keys of a
+/// `[dependencies]` table
+fn dependency_defaults(tokens: &TomlTokens<'_>, entries: &[Entry]) -> Vec<Range<usize>> {
+    let mut removals = Vec::new();
+    for entry in entries {
+        match entry.key() {
+            [_] if tokens.tokens[entry.value_span().start].kind == TokenKind::InlineTableOpen => {
+                let inline = Entry::parse_inline(tokens, entry.value_span().start);
+                if inline.iter().any(|e| e.key() == ["workspace"]) {
+                    continue;
+                }
+                let Some(i) = inline
+                    .iter()
+                    .position(|e| e.key() == ["default-features"] && is_true(tokens, e))
+                else {
+                    continue;
+                };
+                // Take the separator along, without leaving `{ }` behind
+                if let Some(next) = inline.get(i + 1) {
+                    removals.push(inline[i].span().start..next.span().start);
+                } else if let Some(prev) = i.checked_sub(1).map(|p| &inline[p]) {
+                    removals.push(prev.span().end..inline[i].span().end);
+                }
+            }
+            [name, key] if key == "default-features" && is_true(tokens, entry) => {
+                let siblings = entries.iter().filter(|e| e.key()[0] == *name);
+                let is_workspace = entries.iter().any(|e| {
+                    e.key().len() == 2 && e.key()[0] == *name && e.key()[1] == "workspace"
+                });
+                if !is_workspace && 1 < siblings.count() {
+                    removals.push(line_span(tokens, entry));
+                }
+            }
+            _ => {}
+        }
+    }
+    removals
+}
+
+/// What Cargo infers from `[package]`
+struct Package {
+    name: Option<String>,
+    /// Whether autodiscovery is unaffected by explicit targets, since the 2018 edition
+    is_autodiscovery_independent: bool,
+    autolib: bool,
+    autobins: bool,
+}
+
+impl Package {
+    fn new(tokens: &TomlTokens<'_>, tables: &[Table]) -> Self {
+        let entries = tables
+            .iter()
+            .find(|t| !t.is_array_table() && (t.name() == ["package"] || t.name() == ["project"]))
+            .map(|t| Entry::parse(tokens, t.body_span()))
+            .unwrap_or_default();
+        let value = |key: &str| entries.iter().find(|e| e.key() == [key]);
+        let edition = value("edition")
+            .and_then(|e| string(tokens, e))
+            .and_then(|e| e.parse::<u32>().ok());
+        Self {
+            name: value("name")
+                .and_then(|e| string(tokens, e))
+                .map(str::to_owned),
+            is_autodiscovery_independent: edition.map(|e| 2018 <= e).unwrap_or(false),
+            autolib: value("autolib")
+                .map(|e| tokens.tokens[e.value_span().start].raw != "false")
+                .unwrap_or(true),
+            autobins: value("autobins")
+                .map(|e| tokens.tokens[e.value_span().start].raw != "false")
+                .unwrap_or(true),
+        }
+    }
+
+    /// Whether a `[[bin]]` only holds what Cargo would discover on its own
+    fn is_autodiscovered_bin(&self, tokens: &TomlTokens<'_>, entries: &[Entry]) -> bool {
+        if !self.autobins || !self.is_autodiscovery_independent {
+            return false;
+        }
+        let Some(name) = entries
+            .iter()
+            .find(|e| e.key() == ["name"])
+            .and_then(|e| string(tokens, e))
+        else {
+            return false;
+        };
+        let default_paths = if self.name.as_deref() == Some(name) {
+            vec!["src/main.rs".to_owned()]
+        } else {
+            vec![
+                format!("src/bin/{name}.rs"),
+                format!("src/bin/{name}/main.rs"),
+            ]
+        };
+        entries.iter().all(|e| match e.key() {
+            [key] if key == "name" => true,
+            [key] if key == "path" => string(tokens, e)
+                .map(|p| default_paths.iter().any(|d| d == p))
+                .unwrap_or(false),
+            _ => false,
+        })
+    }
+}
+
+fn is_true(tokens: &TomlTokens<'_>, entry: &Entry) -> bool {
+    let value = &tokens.tokens[entry.value_span()];
+    value.len() == 1 && value[0].kind == TokenKind::Scalar && value[0].raw == "true"
+}
+
+fn string<'t>(tokens: &'t TomlTokens<'_>, entry: &Entry) -> Option<&'t str> {
+    let value = &tokens.tokens[entry.value_span()];
+    match value {
+        [value] if value.kind == TokenKind::Scalar => value.decoded.as_deref(),
+        _ => None,
+    }
+}
+
+/// An entry's line, keeping its leading comments
+fn line_span(tokens: &TomlTokens<'_>, entry: &Entry) -> Range<usize> {
+    let mut start = entry.key_span().start;
+    while entry.span().start < start && tokens.tokens[start - 1].kind == TokenKind::Whitespace {
+        start -= 1;
+    }
+    start..entry.span().end
+}
+
+#[cfg(test)]
+mod test {
+    use snapbox::IntoData;
+    use snapbox::assert_data_eq;
+    use snapbox::str;
+
+    #[track_caller]
+    fn valid(input: &str, expected: impl IntoData) {
+        let mut tokens = crate::toml::TomlTokens::parse(input);
+        super::remove_redundant_defaults(&mut tokens, &[]);
+        let actual = tokens.to_string();
+
+        assert_data_eq!(&actual, expected);
+
+        let (_, errors) = toml::de::DeTable::parse_recoverable(&actual);
+        if !errors.is_empty() {
+            use std::fmt::Write as _;
+            let mut result = String::new();
+            writeln!(&mut result, "---").unwrap();
+            for error in errors {
+                writeln!(&mut result, "{error}").unwrap();
+                writeln!(&mut result, "---").unwrap();
+            }
+            panic!("failed to parse\n---\n{actual}\n{result}");
+        }
+    }
+
+    #[test]
+    fn empty() {
+        valid("", str![]);
+    }
+
+    #[test]
+    fn package() {
+        valid(
+            r#"
+[package]
+name = "foo"
+# Release to crates.io
+publish = true # for now
+autobins = true
+autotests = false
+edition = "2021"
+
+[lib]
+path = "src/lib.rs"
+
+[[bin]]
+name = "foo"
+path = "src/main.rs"
+
+[[bin]]
+name = "helper"
+
+[[bin]]
+name = "tool"
+path = "src/tools/tool.rs"
+
+[dependencies]
+"#,
+            str![[r#"
+
+[package]
+name = "foo"
+# Release to crates.io
+autotests = false
+edition = "2021"
+
+[[bin]]
+name = "tool"
+path = "src/tools/tool.rs"
+
+[dependencies]
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_targets_affecting_autodiscovery() {
+        valid(
+            r#"
+[package]
+name = "foo"
+
+[lib]
+path = "src/lib.rs"
+crate-type = ["cdylib"]
+
+[[bin]]
+name = "foo"
+"#,
+            str![[r#"
+
+[package]
+name = "foo"
+
+[lib]
+crate-type = ["cdylib"]
+
+[[bin]]
+name = "foo"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn remove_tables_with_comment_blocks() {
+        valid(
+            r#"
+[package]
+name = "foo"
+
+# The library is the default one,
+# spelled out for clarity
+[lib]
+path = "src/lib.rs"
+
+[dependencies]
+"#,
+            str![[r#"
+
+[package]
+name = "foo"
+
+[dependencies]
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_lib_path_without_autolib() {
+        valid(
+            r#"
+[package]
+name = "foo"
+autolib = false
+
+[lib]
+path = "src/lib.rs"
+"#,
+            str![[r#"
+
+[package]
+name = "foo"
+autolib = false
+
+[lib]
+path = "src/lib.rs"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn dependencies() {
+        valid(
+            r#"
+[dependencies]
+serde = { version = "1", default-features = true, features = ["derive"] }
+toml = { version = "1", default-features = true }
+regex = { default-features = true }
+anyhow = { workspace = true, default-features = true }
+libc.version = "0.2"
+libc.default-features = true
+
+[dev-dependencies.snapbox]
+version = "0.6"
+default-features = true # why not
+
+[build-dependencies.cc]
+default-features = true
+"#,
+            str![[r#"
+
+[dependencies]
+serde = { version = "1", features = ["derive"] }
+toml = { version = "1" }
+regex = { default-features = true }
+anyhow = { workspace = true, default-features = true }
+libc.version = "0.2"
+
+[dev-dependencies.snapbox]
+version = "0.6"
+
+[build-dependencies.cc]
+default-features = true
+
+"#]],
+        );
+    }
+}
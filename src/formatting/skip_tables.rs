@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::toml::Entry;
+use crate::toml::Table;
+use crate::toml::TomlTokens;
+
+/// Restores the tables matching `patterns` to how they are in `original`.
+///
+/// Patterns are dotted table names where `*` matches any single key, like
+/// `workspace.metadata.*`.  A pattern also matches the subtables of the
+/// tables it names, and keys into them from other tables, like
+/// `metadata.release.x = 1` in `[package]`.
+///
+/// This undoes edits within those tables, while passes that add, remove or
+/// rename tables leave them alone through [`SkipTables`].  Tables are paired up
+/// by name and occurrence, so repeated `[[bin]]` entries are restored
+/// individually.  Should the tables still have changed, nothing is restored and
+/// this is reported in `warnings`, as pairing them up would lose or duplicate
+/// tables.
+#[tracing::instrument(skip(original, warnings))]
+pub fn restore_skipped_tables<'i>(
+    tokens: &mut TomlTokens<'i>,
+    original: &TomlTokens<'i>,
+    patterns: &[String],
+    warnings: &mut Vec<String>,
+) {
+    let skip = SkipTables::new(patterns);
+    if skip.is_empty() {
+        return;
+    }
+
+    let original_tables = Table::new(original);
+    let original_spans = occurrences(&original_tables)
+        .filter(|(table, _)| skip.contains(table.name()))
+        .map(|(table, key)| (key, restored_span(table)))
+        .collect::<HashMap<_, _>>();
+    let tables = Table::new(tokens);
+    let skipped = occurrences(&tables)
+        .filter(|(table, _)| skip.contains(table.name()))
+        .collect::<Vec<_>>();
+    // Occurrences are unique, so equal counts and containment make equal sets
+    let is_unchanged = skipped.len() == original_spans.len()
+        && skipped
+            .iter()
+            .all(|(_, key)| original_spans.contains_key(key));
+    if !is_unchanged {
+        warnings.push(
+            "formatting adds or removes tables matching `skip_tables`, formatting them anyway"
+                .to_owned(),
+        );
+        return;
+    }
+
+    let mut restores = skipped
+        .into_iter()
+        .map(|(table, key)| (restored_span(table), original_spans[&key].clone()))
+        .collect::<Vec<_>>();
+    let original_entries = skipped_entries(original, &original_tables, &skip)
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+    restores.extend(
+        skipped_entries(tokens, &tables, &skip)
+            .into_iter()
+            .filter_map(|(key, span)| Some((span, original_entries.get(&key)?.clone()))),
+    );
+
+    // Back to front so earlier spans stay valid
+    restores.sort_by_key(|(span, _)| span.start);
+    for (span, original_span) in restores.into_iter().rev() {
+        let restored = original.tokens[original_span].iter().cloned();
+        tokens.tokens.splice(span, restored);
+    }
+}
+
+/// The tables matching `skip_tables` patterns, for passes to leave alone
+pub(crate) struct SkipTables {
+    patterns: Vec<Vec<String>>,
+}
+
+impl SkipTables {
+    pub(crate) fn new(patterns: &[String]) -> Self {
+        Self {
+            patterns: patterns.iter().map(|p| split_pattern(p)).collect(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Whether the table or key at `path` is within a skipped table
+    pub(crate) fn contains(&self, path: &[String]) -> bool {
+        self.patterns
+            .iter()
+            .any(|p| p.len() <= path.len() && matches_prefix(path, p))
+    }
+
+    /// Whether the key at `path` is within a skipped table or holds one, like `metadata`
+    /// holding `package.metadata.release` in `[package]`
+    pub(crate) fn overlaps(&self, path: &[String]) -> bool {
+        self.patterns.iter().any(|p| matches_prefix(path, p))
+    }
+}
+
+/// A table with its leading comments, but not the next table's
+fn restored_span(table: &Table) -> Range<usize> {
+    table.span().start..table.body_span().end
+}
+
+/// Pairs each table with its name and how many same-named tables precede it
+fn occurrences(tables: &[Table]) -> impl Iterator<Item = (&Table, Occurrence)> {
+    let mut seen = HashMap::<&[String], usize>::new();
+    tables.iter().map(move |table| {
+        let count = seen.entry(table.name()).or_default();
+        let key = (table.name().to_vec(), *count);
+        *count += 1;
+        (table, key)
+    })
+}
+
+/// A table's name and how many same-named tables precede it
+type Occurrence = (Vec<String>, usize);
+
+/// An entry's table, `None` before the first header, and its key
+type EntryKey = (Option<Occurrence>, Vec<String>);
+
+/// Entries of other tables that are within or hold skipped tables
+fn skipped_entries(
+    tokens: &TomlTokens<'_>,
+    tables: &[Table],
+    skip: &SkipTables,
+) -> Vec<(EntryKey, Range<usize>)> {
+    let root_end = tables
+        .first()
+        .map(|t| t.span().start)
+        .unwrap_or(tokens.len());
+    let bodies = std::iter::once((None, &[][..], 0..root_end)).chain(
+        occurrences(tables)
+            .filter(|(table, _)| !skip.contains(table.name()))
+            .map(|(table, key)| (Some(key), table.name(), table.body_span())),
+    );
+
+    let mut entries = Vec::new();
+    for (table, name, body) in bodies {
+        for entry in Entry::parse(tokens, body) {
+            if skip.overlaps(&[name, entry.key()].concat()) {
+                entries.push(((table.clone(), entry.key().to_vec()), entry.span()));
+            }
+        }
+    }
+    entries
+}
+
+/// Whether `name` and `pattern` agree on the keys they both have
+fn matches_prefix(name: &[String], pattern: &[String]) -> bool {
+    pattern.iter().zip(name).all(|(p, n)| p == "*" || p == n)
+}
+
+/// Splits a dotted pattern, honoring quoted keys like `target.'cfg(unix)'`
+pub(crate) fn split_pattern(pattern: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    let mut key = String::new();
+    let mut quote = None;
+    for c in pattern.chars() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('.', None) => keys.push(std::mem::take(&mut key).trim().to_owned()),
+            (c, _) => key.push(c),
+        }
+    }
+    keys.push(key.trim().to_owned());
+    keys
+}
+
+#[cfg(test)]
+mod test {
+    use snapbox::IntoData;
+    use snapbox::assert_data_eq;
+    use snapbox::str;
+
+    #[track_caller]
+    fn valid(input: &str, patterns: &[&str], expected: impl IntoData) {
+        let patterns = patterns.iter().map(|p| (*p).to_owned()).collect::<Vec<_>>();
+        let original = crate::toml::TomlTokens::parse(input);
+        let mut tokens = crate::toml::TomlTokens::parse(input);
+        crate::formatting::normalize_strings(&mut tokens);
+        crate::formatting::normalize_space_separators(&mut tokens);
+        crate::formatting::constrain_blank_lines(&mut tokens, 0, 1);
+        let mut warnings = Vec::new();
+        super::restore_skipped_tables(&mut tokens, &original, &patterns, &mut warnings);
+        let actual = tokens.to_string();
+
+        assert_data_eq!(&actual, expected);
+        assert!(warnings.is_empty(), "{warnings:?}");
+
+        let (_, errors) = toml::de::DeTable::parse_recoverable(&actual);
+        if !errors.is_empty() {
+            use std::fmt::Write as _;
+            let mut result = String::new();
+            writeln!(&mut result, "---").unwrap();
+            for error in errors {
+                writeln!(&mut result, "{error}").unwrap();
+                writeln!(&mut result, "---").unwrap();
+            }
+            panic!("failed to parse\n---\n{actual}\n{result}");
+        }
+    }
+
+    #[test]
+    fn empty() {
+        valid("", &["package"], str![]);
+    }
+
+    #[test]
+    fn no_patterns() {
+        valid(
+            r#"
+[ package ]
+name = 'foo'
+"#,
+            &[],
+            str![[r#"
+[package]
+name = "foo"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn skip_exact_table() {
+        valid(
+            r#"
+[package]
+name = 'foo'
+
+[package.metadata.release]
+pre-release-replacements = [ 'a' ]
+
+
+[dependencies]
+serde = 'x'
+"#,
+            &["package.metadata.release"],
+            str![[r#"
+[package]
+name = "foo"
+
+[package.metadata.release]
+pre-release-replacements = [ 'a' ]
+
+
+[dependencies]
+serde = "x"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn skip_wildcard_and_subtables() {
+        valid(
+            r#"
+[workspace.metadata.foo]
+a = 'a'
+[workspace.metadata.foo.nested]
+b = 'b'
+[workspace.metadatax]
+c = 'c'
+"#,
+            &["workspace.metadata.*"],
+            str![[r#"
+[workspace.metadata.foo]
+a = 'a'
+[workspace.metadata.foo.nested]
+b = 'b'
+[workspace.metadatax]
+c = "c"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn skip_keeps_leading_comment() {
+        valid(
+            r#"
+a = 'a'
+# Keep me
+[ skip ]
+b = 'b'
+"#,
+            &["skip"],
+            str![[r#"
+a = "a"
+# Keep me
+[ skip ]
+b = 'b'
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn skip_array_tables_individually() {
+        valid(
+            r#"
+[[bin]]
+name = 'a'
+[[ bin ]]
+name = 'b'
+"#,
+            &["bin"],
+            str![[r#"
+[[bin]]
+name = 'a'
+[[ bin ]]
+name = 'b'
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn skip_quoted_pattern() {
+        valid(
+            r#"
+[target.'cfg(unix)'.dependencies]
+a = 'a'
+[target.'cfg(windows)'.dependencies]
+b = 'b'
+"#,
+            &["target.'cfg(unix)'"],
+            str![[r#"
+[target.'cfg(unix)'.dependencies]
+a = 'a'
+[target."cfg(windows)".dependencies]
+b = "b"
+
+"#]],
+        );
+    }
+}
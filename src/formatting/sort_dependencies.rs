@@ -0,0 +1,291 @@
+use super::manifest::is_dependency_table;
+use crate::toml::Entry;
+use crate::toml::Table;
+use crate::toml::TomlTokens;
+
+/// Sorts the entries of dependency tables by name.
+///
+/// Comments directly above an entry move with it.  Blank lines, and comments
+/// separated from entries by blank lines, split a table into groups that are
+/// sorted independently.
+#[tracing::instrument]
+pub fn sort_dependencies(tokens: &mut TomlTokens<'_>) {
+    let tables = Table::new(tokens);
+    for table in tables.iter().rev() {
+        if !is_dependency_table(table.name()) {
+            continue;
+        }
+
+        let entries = Entry::parse(tokens, table.body_span());
+        for run in Entry::runs(&entries).into_iter().rev() {
+            // Only compare the dependency name so `serde.version` and
+            // `serde.features` keep their relative order
+            Entry::sort_by(tokens, run, |a, b| a.key()[0].cmp(&b.key()[0]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use snapbox::IntoData;
+    use snapbox::assert_data_eq;
+    use snapbox::str;
+
+    #[track_caller]
+    fn valid(input: &str, expected: impl IntoData) {
+        let mut tokens = crate::toml::TomlTokens::parse(input);
+        super::sort_dependencies(&mut tokens);
+        let actual = tokens.to_string();
+
+        assert_data_eq!(&actual, expected);
+
+        let (_, errors) = toml::de::DeTable::parse_recoverable(&actual);
+        if !errors.is_empty() {
+            use std::fmt::Write as _;
+            let mut result = String::new();
+            writeln!(&mut result, "---").unwrap();
+            for error in errors {
+                writeln!(&mut result, "{error}").unwrap();
+                writeln!(&mut result, "---").unwrap();
+            }
+            panic!("failed to parse\n---\n{actual}\n{result}");
+        }
+    }
+
+    #[test]
+    fn empty() {
+        valid("", str![]);
+    }
+
+    #[test]
+    fn sorted() {
+        valid(
+            r#"
+[dependencies]
+a = "1"
+b = "1"
+"#,
+            str![[r#"
+
+[dependencies]
+a = "1"
+b = "1"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn unsorted() {
+        valid(
+            r#"
+[dependencies]
+toml = "1"
+serde = { version = "1", features = ["derive"] }
+anyhow = "1"
+"#,
+            str![[r#"
+
+[dependencies]
+anyhow = "1"
+serde = { version = "1", features = ["derive"] }
+toml = "1"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn all_dependency_tables() {
+        valid(
+            r#"
+[dev-dependencies]
+b = "1"
+a = "1"
+
+[build-dependencies]
+b = "1"
+a = "1"
+
+[workspace.dependencies]
+b = "1"
+a = "1"
+
+[target.'cfg(unix)'.dependencies]
+b = "1"
+a = "1"
+
+[package]
+version = "1"
+name = "foo"
+
+[target.'cfg(unix)'.features]
+b = []
+a = []
+"#,
+            str![[r#"
+
+[dev-dependencies]
+a = "1"
+b = "1"
+
+[build-dependencies]
+a = "1"
+b = "1"
+
+[workspace.dependencies]
+a = "1"
+b = "1"
+
+[target.'cfg(unix)'.dependencies]
+a = "1"
+b = "1"
+
+[package]
+version = "1"
+name = "foo"
+
+[target.'cfg(unix)'.features]
+b = []
+a = []
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn leading_comments_move() {
+        valid(
+            r#"
+[dependencies] # header
+# For b
+# really
+b = "1" # trailing b
+a = "1" # trailing a
+"#,
+            str![[r#"
+
+[dependencies] # header
+a = "1" # trailing a
+# For b
+# really
+b = "1" # trailing b
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn groups_sorted_independently() {
+        valid(
+            r#"
+[dependencies]
+d = "1"
+c = "1"
+
+# Group comment
+
+b = "1"
+a = "1"
+# Dangling
+
+z = "1"
+y = "1"
+"#,
+            str![[r#"
+
+[dependencies]
+c = "1"
+d = "1"
+
+# Group comment
+
+a = "1"
+b = "1"
+# Dangling
+
+y = "1"
+z = "1"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn last_entry_without_newline() {
+        valid(
+            r#"[dependencies]
+b = "1"
+a = "1""#,
+            str![[r#"
+[dependencies]
+a = "1"
+b = "1"
+"#]],
+        );
+    }
+
+    #[test]
+    fn dotted_keys_keep_order() {
+        valid(
+            r#"
+[dependencies]
+serde.version = "1"
+anyhow = "1"
+serde.features = ["derive"]
+"#,
+            str![[r#"
+
+[dependencies]
+anyhow = "1"
+serde.version = "1"
+serde.features = ["derive"]
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn multiline_values() {
+        valid(
+            r#"
+[dependencies]
+b = { version = "1", features = [
+    "x",
+] }
+a = "1"
+"#,
+            str![[r#"
+
+[dependencies]
+a = "1"
+b = { version = "1", features = [
+    "x",
+] }
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn next_table_comment_stays() {
+        valid(
+            r#"
+[dependencies]
+b = "1"
+a = "1"
+# About dev-dependencies
+[dev-dependencies]
+"#,
+            str![[r#"
+
+[dependencies]
+a = "1"
+b = "1"
+# About dev-dependencies
+[dev-dependencies]
+
+"#]],
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use super::skip_tables::split_pattern;
+use crate::toml::Array;
+use crate::toml::Entry;
+use crate::toml::Table;
+use crate::toml::TokenKind;
+use crate::toml::TomlTokens;
+
+/// Sorts the string arrays at the dotted key `paths`, like `workspace.members`.
+///
+/// Comments directly above or after an element move with it.  Arrays with anything
+/// other than strings are left alone.
+#[tracing::instrument]
+pub fn sort_string_arrays(tokens: &mut TomlTokens<'_>, paths: &[String]) {
+    let paths = paths.iter().map(|p| split_pattern(p)).collect::<Vec<_>>();
+    if paths.is_empty() {
+        return;
+    }
+
+    let tables = Table::new(tokens);
+    let root_end = tables
+        .first()
+        .map(|t| t.span().start)
+        .unwrap_or(tokens.len());
+    let bodies = std::iter::once((&[][..], 0..root_end)).chain(
+        tables
+            .iter()
+            .filter(|t| !t.is_array_table())
+            .map(|t| (t.name(), t.body_span())),
+    );
+    let mut arrays = Vec::new();
+    for (name, body) in bodies {
+        for entry in Entry::parse(tokens, body) {
+            let open = entry.value_span().start;
+            if tokens.tokens.get(open).map(|t| t.kind) != Some(TokenKind::ArrayOpen) {
+                continue;
+            }
+            let path = name.iter().chain(entry.key()).collect::<Vec<_>>();
+            if paths.iter().any(|p| p.iter().eq(path.iter().copied())) {
+                arrays.push(open);
+            }
+        }
+    }
+
+    // Back to front so earlier spans stay valid
+    for open in arrays.into_iter().rev() {
+        sort_array(tokens, open);
+    }
+}
+
+fn sort_array(tokens: &mut TomlTokens<'_>, open: usize) {
+    let Some(array) = Array::parse(tokens, open) else {
+        return;
+    };
+    let values = array
+        .elements()
+        .iter()
+        .map(|e| tokens.tokens[e.value()].decoded.as_deref())
+        .collect::<Option<Vec<_>>>();
+    let Some(values) = values else {
+        return;
+    };
+
+    let mut order = (0..values.len()).collect::<Vec<_>>();
+    order.sort_by_key(|i| values[*i]);
+    if order.iter().copied().eq(0..values.len()) {
+        return;
+    }
+
+    array.rewrite(tokens, &order);
+}
+
+#[cfg(test)]
+mod test {
+    use snapbox::IntoData;
+    use snapbox::assert_data_eq;
+    use snapbox::str;
+
+    #[track_caller]
+    fn valid(input: &str, paths: &[&str], expected: impl IntoData) {
+        let paths = paths.iter().map(|p| (*p).to_owned()).collect::<Vec<_>>();
+        let mut tokens = crate::toml::TomlTokens::parse(input);
+        super::sort_string_arrays(&mut tokens, &paths);
+        let actual = tokens.to_string();
+
+        assert_data_eq!(&actual, expected);
+
+        let (_, errors) = toml::de::DeTable::parse_recoverable(&actual);
+        if !errors.is_empty() {
+            use std::fmt::Write as _;
+            let mut result = String::new();
+            writeln!(&mut result, "---").unwrap();
+            for error in errors {
+                writeln!(&mut result, "{error}").unwrap();
+                writeln!(&mut result, "---").unwrap();
+            }
+            panic!("failed to parse\n---\n{actual}\n{result}");
+        }
+    }
+
+    const DEFAULT: &[&str] = &[
+        "workspace.members",
+        "workspace.exclude",
+        "workspace.default-members",
+        "package.include",
+        "package.exclude",
+        "package.keywords",
+        "package.categories",
+    ];
+
+    #[test]
+    fn empty() {
+        valid("", DEFAULT, str![]);
+    }
+
+    #[test]
+    fn sort() {
+        valid(
+            r#"
+[workspace]
+members = [
+    "crates/toml",
+    # Vendored
+    "crates/toml_edit", # for now
+    "crates/benchmarks",
+]
+exclude = ["target", "docs"]
+
+[package]
+keywords = ["toml", "cargo", "formatter"]
+authors = ["b", "a"]
+"#,
+            DEFAULT,
+            str![[r#"
+
+[workspace]
+members = [
+    "crates/benchmarks",
+    "crates/toml",
+    # Vendored
+    "crates/toml_edit", # for now
+]
+exclude = ["docs", "target"]
+
+[package]
+keywords = ["cargo", "formatter", "toml"]
+authors = ["b", "a"]
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn dotted_keys() {
+        valid(
+            r#"
+package.categories = ["parsing", "development-tools"]
+
+[workspace]
+package.exclude = ["b", "a"]
+"#,
+            &["package.categories", "workspace.package.exclude"],
+            str![[r#"
+
+package.categories = ["development-tools", "parsing"]
+
+[workspace]
+package.exclude = ["a", "b"]
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_mixed_arrays() {
+        valid(
+            r#"
+[package]
+keywords = ["b", 1, "a"]
+"#,
+            DEFAULT,
+            str![[r#"
+
+[package]
+keywords = ["b", 1, "a"]
+
+"#]],
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use crate::toml::Table;
+use crate::toml::TomlTokens;
+
+/// Moves subtables, with their leading comments, to directly after their parent table.
+///
+/// The parent is the table with the longest matching name prefix.  Siblings keep their
+/// relative order.  A subtable of an array of tables belongs to the element it follows,
+/// so it only moves within that element.
+#[tracing::instrument]
+pub fn group_subtables(tokens: &mut TomlTokens<'_>) {
+    let tables = Table::new(tokens);
+
+    let mut roots = Vec::new();
+    let mut children = vec![Vec::new(); tables.len()];
+    for i in 0..tables.len() {
+        match find_parent(&tables, i) {
+            Some(parent) => children[parent].push(i),
+            None => roots.push(i),
+        }
+    }
+
+    let mut order = Vec::with_capacity(tables.len());
+    let mut stack = roots.into_iter().rev().collect::<Vec<_>>();
+    while let Some(i) = stack.pop() {
+        order.push(i);
+        stack.extend(children[i].iter().rev());
+    }
+    if order.iter().enumerate().all(|(i, o)| i == *o) {
+        return;
+    }
+
+    Table::reorder(tokens, &tables, &order);
+}
+
+fn find_parent(tables: &[Table], i: usize) -> Option<usize> {
+    let name = tables[i].name();
+    (1..name.len()).rev().find_map(|len| {
+        let prefix = &name[..len];
+        tables[..i]
+            .iter()
+            .rposition(|t| t.is_array_table() && t.name() == prefix)
+            .or_else(|| {
+                tables
+                    .iter()
+                    .position(|t| !t.is_array_table() && t.name() == prefix)
+            })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use snapbox::IntoData;
+    use snapbox::assert_data_eq;
+    use snapbox::str;
+
+    #[track_caller]
+    fn valid(input: &str, expected: impl IntoData) {
+        let mut tokens = crate::toml::TomlTokens::parse(input);
+        super::group_subtables(&mut tokens);
+        let actual = tokens.to_string();
+
+        assert_data_eq!(&actual, expected);
+
+        let (_, errors) = toml::de::DeTable::parse_recoverable(&actual);
+        if !errors.is_empty() {
+            use std::fmt::Write as _;
+            let mut result = String::new();
+            writeln!(&mut result, "---").unwrap();
+            for error in errors {
+                writeln!(&mut result, "{error}").unwrap();
+                writeln!(&mut result, "---").unwrap();
+            }
+            panic!("failed to parse\n---\n{actual}\n{result}");
+        }
+    }
+
+    #[test]
+    fn empty() {
+        valid("", str![]);
+    }
+
+    #[test]
+    fn already_grouped() {
+        valid(
+            r#"
+[dependencies]
+a = "1"
+
+[dependencies.foo]
+version = "1"
+
+[features]
+"#,
+            str![[r#"
+
+[dependencies]
+a = "1"
+
+[dependencies.foo]
+version = "1"
+
+[features]
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn move_after_parent() {
+        valid(
+            r#"
+[dependencies]
+a = "1"
+
+[features]
+default = []
+
+# About foo
+[dependencies.foo]
+version = "1"
+
+[dev-dependencies]
+b = "1"
+
+[dependencies.bar]
+version = "1"
+"#,
+            str![[r#"
+
+[dependencies]
+a = "1"
+
+# About foo
+[dependencies.foo]
+version = "1"
+
+[dependencies.bar]
+version = "1"
+
+[features]
+default = []
+
+[dev-dependencies]
+b = "1"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn comment_blocks_move_with_subtables() {
+        valid(
+            r#"
+[a]
+x = 1
+
+# About b,
+# over two lines
+[b]
+y = 2
+
+# About a.sub,
+# over two lines
+[a.sub]
+z = 3
+"#,
+            str![[r#"
+
+[a]
+x = 1
+
+# About a.sub,
+# over two lines
+[a.sub]
+z = 3
+
+# About b,
+# over two lines
+[b]
+y = 2
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn nested_subtables() {
+        valid(
+            r#"
+[package]
+name = "foo"
+
+[dependencies]
+
+[package.metadata.docs.rs]
+all-features = true
+
+[package.metadata]
+foo = 1
+"#,
+            str![[r#"
+
+[package]
+name = "foo"
+
+[package.metadata]
+foo = 1
+
+[package.metadata.docs.rs]
+all-features = true
+
+[dependencies]
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn subtable_before_parent() {
+        valid(
+            r#"
+[a.b]
+x = 1
+
+[c]
+
+[a]
+y = 1
+"#,
+            str![[r#"
+
+[c]
+
+[a]
+y = 1
+
+[a.b]
+x = 1
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn missing_parent_uses_shorter_prefix() {
+        valid(
+            r#"
+[workspace]
+
+[profile.release]
+
+[workspace.metadata.foo]
+"#,
+            str![[r#"
+
+[workspace]
+
+[workspace.metadata.foo]
+
+[profile.release]
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn array_table_subtables_stay_with_element() {
+        valid(
+            r#"
+[[bin]]
+name = "a"
+
+[other]
+
+[bin.metadata]
+x = 1
+
+[[bin]]
+name = "b"
+
+[bin.metadata]
+x = 2
+"#,
+            str![[r#"
+
+[[bin]]
+name = "a"
+
+[bin.metadata]
+x = 1
+
+[other]
+
+[[bin]]
+name = "b"
+
+[bin.metadata]
+x = 2
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn array_tables_under_std_parent() {
+        valid(
+            r#"
+[servers]
+
+[other]
+
+[[servers.production]]
+ip = "1"
+
+[[servers.production]]
+ip = "2"
+"#,
+            str![[r#"
+
+[servers]
+
+[[servers.production]]
+ip = "1"
+
+[[servers.production]]
+ip = "2"
+
+[other]
+
+"#]],
+        );
+    }
+}
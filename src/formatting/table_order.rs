@@ -0,0 +1,271 @@
+use crate::toml::Table;
+use crate::toml::TomlTokens;
+
+/// Moves tables, with their leading comments, into `order` by their top-level key.
+///
+/// Tables not in `order` go last.  Otherwise, tables keep their relative order, so
+/// `[package.metadata]` stays after `[package]` and `[[bin]]` entries aren't shuffled.
+#[tracing::instrument]
+pub fn reorder_tables(tokens: &mut TomlTokens<'_>, order: &[String]) {
+    let tables = Table::new(tokens);
+    if tables.len() < 2 {
+        return;
+    }
+
+    let rank = |table: &Table| {
+        table
+            .name()
+            .first()
+            .and_then(|name| order.iter().position(|o| o == name))
+            .unwrap_or(order.len())
+    };
+    let mut new_order = (0..tables.len()).collect::<Vec<_>>();
+    new_order.sort_by_key(|i| rank(&tables[*i]));
+    if new_order.iter().enumerate().all(|(i, o)| i == *o) {
+        return;
+    }
+
+    Table::reorder(tokens, &tables, &new_order);
+}
+
+#[cfg(test)]
+mod test {
+    use snapbox::IntoData;
+    use snapbox::assert_data_eq;
+    use snapbox::str;
+
+    #[track_caller]
+    fn valid(input: &str, expected: impl IntoData) {
+        let order = crate::config::Config::default().table_order;
+        let mut tokens = crate::toml::TomlTokens::parse(input);
+        super::reorder_tables(&mut tokens, &order);
+        let actual = tokens.to_string();
+
+        assert_data_eq!(&actual, expected);
+
+        let (_, errors) = toml::de::DeTable::parse_recoverable(&actual);
+        if !errors.is_empty() {
+            use std::fmt::Write as _;
+            let mut result = String::new();
+            writeln!(&mut result, "---").unwrap();
+            for error in errors {
+                writeln!(&mut result, "{error}").unwrap();
+                writeln!(&mut result, "---").unwrap();
+            }
+            panic!("failed to parse\n---\n{actual}\n{result}");
+        }
+    }
+
+    #[test]
+    fn empty() {
+        valid("", str![]);
+    }
+
+    #[test]
+    fn ordered() {
+        valid(
+            r#"
+[package]
+name = "foo"
+
+[dependencies]
+a = "1"
+"#,
+            str![[r#"
+
+[package]
+name = "foo"
+
+[dependencies]
+a = "1"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn reorder() {
+        valid(
+            r#"cargo-features = ["foo"]
+
+[workspace]
+
+[dev-dependencies]
+b = "1"
+
+[dependencies]
+a = "1"
+
+[package]
+name = "foo"
+"#,
+            str![[r#"
+cargo-features = ["foo"]
+
+[package]
+name = "foo"
+
+[dependencies]
+a = "1"
+
+[dev-dependencies]
+b = "1"
+
+[workspace]
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn subtables_follow_their_group() {
+        valid(
+            r#"
+[dependencies]
+a = "1"
+
+[package.metadata.docs.rs]
+all-features = true
+
+[package]
+name = "foo"
+
+[target.'cfg(unix)'.dependencies]
+b = "1"
+
+[lints]
+workspace = true
+"#,
+            str![[r#"
+
+[package.metadata.docs.rs]
+all-features = true
+
+[package]
+name = "foo"
+
+[dependencies]
+a = "1"
+
+[target.'cfg(unix)'.dependencies]
+b = "1"
+
+[lints]
+workspace = true
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn array_tables_keep_order() {
+        valid(
+            r#"
+[[bench]]
+name = "z"
+
+[[bin]]
+name = "b"
+
+[lib]
+path = "lib.rs"
+
+[[bin]]
+name = "a"
+"#,
+            str![[r#"
+
+[lib]
+path = "lib.rs"
+
+[[bin]]
+name = "b"
+
+[[bin]]
+name = "a"
+
+[[bench]]
+name = "z"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn unknown_tables_last() {
+        valid(
+            r#"
+[patch.crates-io]
+foo = { path = "foo" }
+
+[badges]
+maintenance = "none"
+
+[package]
+name = "foo"
+"#,
+            str![[r#"
+
+[package]
+name = "foo"
+
+[patch.crates-io]
+foo = { path = "foo" }
+
+[badges]
+maintenance = "none"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn leading_comments_move_with_tables() {
+        valid(
+            r#"
+# About dependencies
+[dependencies] # header
+a = "1"
+# Leading package
+[package]
+name = "foo"
+"#,
+            str![[r#"
+
+# Leading package
+[package]
+name = "foo"
+# About dependencies
+[dependencies] # header
+a = "1"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn comment_blocks_move_with_tables() {
+        valid(
+            r#"
+[dependencies]
+a = "1"
+
+# line one
+# line two
+[package]
+name = "foo"
+"#,
+            str![[r#"
+
+# line one
+# line two
+[package]
+name = "foo"
+
+[dependencies]
+a = "1"
+
+"#]],
+        );
+    }
+}
@@ -0,0 +1,504 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use super::skip_tables::SkipTables;
+use crate::toml::Encoding;
+use crate::toml::Entry;
+use crate::toml::Table;
+use crate::toml::TokenKind;
+use crate::toml::TomlToken;
+use crate::toml::TomlTokens;
+
+/// Reprints the `cfg(...)` predicates of `[target.'cfg(...)']` headers canonically.
+///
+/// Predicates are single-quoted with `, ` between operands, like
+/// `[target.'cfg(any(unix, target_os = "wasi"))'.dependencies]`.  With `sort`, the
+/// operands of `any` and `all` are sorted.
+///
+/// Tables whose predicates become identical are merged.  When they define the same
+/// keys, their predicates are left as they were, as are tables in or becoming
+/// `skip_tables`.
+#[tracing::instrument]
+pub fn normalize_target_cfgs(tokens: &mut TomlTokens<'_>, sort: bool, skip_tables: &[String]) {
+    let skip = SkipTables::new(skip_tables);
+    let tables = Table::new(tokens);
+    let canonical = tables
+        .iter()
+        .map(|t| match t.name() {
+            [target, cfg, ..] if target == "target" => canonical_cfg(cfg, sort),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let conflicts = conflicting_cfgs(tokens, &tables, &canonical);
+
+    for (table, cfg) in tables.iter().zip(&canonical) {
+        let Some(cfg) = cfg else {
+            continue;
+        };
+        let mut name = table.name().to_vec();
+        name[1] = cfg.clone();
+        if conflicts.contains(cfg) || skip.contains(table.name()) || skip.contains(&name) {
+            continue;
+        }
+        let Some(key) = (table.header_span())
+            .filter(|i| tokens.tokens[*i].kind == TokenKind::SimpleKey)
+            .nth(1)
+        else {
+            continue;
+        };
+        let token = &mut tokens.tokens[key];
+        let raw = format!("'{cfg}'");
+        if token.raw != raw {
+            token.raw = Cow::Owned(raw);
+            token.decoded = Some(Cow::Owned(cfg.clone()));
+            token.encoding = Some(Encoding::LiteralString);
+        }
+    }
+
+    while merge_next_duplicate(tokens, &skip) {}
+}
+
+/// Predicates that can't be normalized without defining a key twice
+fn conflicting_cfgs(
+    tokens: &TomlTokens<'_>,
+    tables: &[Table],
+    canonical: &[Option<String>],
+) -> HashSet<String> {
+    let names = tables
+        .iter()
+        .zip(canonical)
+        .map(|(table, cfg)| {
+            let mut name = table.name().to_vec();
+            if let Some(cfg) = cfg {
+                name[1] = cfg.clone();
+            }
+            name
+        })
+        .collect::<Vec<_>>();
+
+    let mut conflicts = Vec::new();
+    let mut defined = HashMap::new();
+    for (i, (table, name)) in tables.iter().zip(&names).enumerate() {
+        if table.is_array_table() {
+            continue;
+        }
+        for entry in Entry::parse(tokens, table.body_span()) {
+            for len in 1..=entry.key().len() {
+                let mut path = name.clone();
+                path.extend_from_slice(&entry.key()[..len]);
+                if defined
+                    .insert(path, i)
+                    .map(|other| other != i)
+                    .unwrap_or(false)
+                {
+                    conflicts.push(name);
+                }
+            }
+        }
+    }
+    conflicts.extend(names.iter().filter(|n| defined.contains_key(*n)));
+
+    conflicts
+        .into_iter()
+        .filter(|name| name.len() >= 2 && name[0] == "target")
+        .map(|name| name[1].clone())
+        .collect()
+}
+
+/// Moves the body of a repeated standard table into its first occurrence
+fn merge_next_duplicate(tokens: &mut TomlTokens<'_>, skip: &SkipTables) -> bool {
+    let tables = Table::new(tokens);
+    for (i, table) in tables.iter().enumerate() {
+        if table.is_array_table() || skip.contains(table.name()) {
+            continue;
+        }
+        let Some(duplicate) = tables[i + 1..]
+            .iter()
+            .find(|t| !t.is_array_table() && t.name() == table.name())
+        else {
+            continue;
+        };
+
+        let mut moved = tokens.tokens[duplicate.span().start..duplicate.header_span().start]
+            .iter()
+            .filter(|t| t.kind != TokenKind::Whitespace || !t.raw.trim().is_empty())
+            .cloned()
+            .collect::<Vec<_>>();
+        let header_comment = tokens.tokens
+            [duplicate.header_span().end..duplicate.body_span().start]
+            .iter()
+            .find(|t| t.kind == TokenKind::Comment);
+        if let Some(comment) = header_comment {
+            moved.extend([comment.clone(), TomlToken::NL]);
+        }
+        moved.extend(tokens.tokens[duplicate.body_span()].iter().cloned());
+        while moved.len() >= 2
+            && moved[moved.len() - 1].kind == TokenKind::Newline
+            && moved[moved.len() - 2].kind == TokenKind::Newline
+        {
+            moved.pop();
+        }
+        if moved.last().map(|t| t.kind) != Some(TokenKind::Newline) {
+            moved.push(TomlToken::NL);
+        }
+
+        let at = Entry::parse(tokens, table.body_span())
+            .last()
+            .map(|e| e.span().end)
+            .unwrap_or(table.body_span().start);
+        if 0 < at && tokens.tokens[at - 1].kind != TokenKind::Newline {
+            moved.insert(0, TomlToken::NL);
+        }
+        let mut start = duplicate.span().start;
+        if duplicate.body_span().end == tokens.len() {
+            // Blank lines separating the last table would be left trailing
+            let mut blank = start;
+            while at < blank
+                && matches!(
+                    tokens.tokens[blank - 1].kind,
+                    TokenKind::Newline | TokenKind::Whitespace
+                )
+            {
+                blank -= 1;
+            }
+            start = (blank..start)
+                .find(|&i| tokens.tokens[i].kind == TokenKind::Newline)
+                .map(|i| i + 1)
+                .unwrap_or(start);
+        }
+        tokens.tokens.drain(start..duplicate.body_span().end);
+        tokens.tokens.splice(at..at, moved);
+        return true;
+    }
+    false
+}
+
+/// Parses `cfg(...)`, returning it reprinted canonically
+fn canonical_cfg(cfg: &str, sort: bool) -> Option<String> {
+    let mut parser = CfgParser { rest: cfg };
+    parser.keyword("cfg")?;
+    parser.punct('(')?;
+    let mut predicate = parser.predicate()?;
+    parser.punct(')')?;
+    if !parser.rest.trim().is_empty() {
+        return None;
+    }
+
+    if sort {
+        predicate.sort();
+    }
+    let canonical = format!("cfg({predicate})");
+    // Must fit in a literal string
+    (!canonical.contains('\'')).then_some(canonical)
+}
+
+enum Cfg {
+    Name(String),
+    KeyValue(String, String),
+    Any(Vec<Self>),
+    All(Vec<Self>),
+    Not(Box<Self>),
+}
+
+impl Cfg {
+    fn sort(&mut self) {
+        match self {
+            Self::Name(_) | Self::KeyValue(..) => {}
+            Self::Any(operands) | Self::All(operands) => {
+                for operand in operands.iter_mut() {
+                    operand.sort();
+                }
+                operands.sort_by_cached_key(|o| o.to_string());
+            }
+            Self::Not(operand) => operand.sort(),
+        }
+    }
+}
+
+impl std::fmt::Display for Cfg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let list = |f: &mut std::fmt::Formatter<'_>, operands: &[Self]| {
+            for (i, operand) in operands.iter().enumerate() {
+                if 0 < i {
+                    f.write_str(", ")?;
+                }
+                operand.fmt(f)?;
+            }
+            Ok(())
+        };
+        match self {
+            Self::Name(name) => f.write_str(name),
+            Self::KeyValue(key, value) => write!(f, "{key} = \"{value}\""),
+            Self::Any(operands) => {
+                f.write_str("any(")?;
+                list(f, operands)?;
+                f.write_str(")")
+            }
+            Self::All(operands) => {
+                f.write_str("all(")?;
+                list(f, operands)?;
+                f.write_str(")")
+            }
+            Self::Not(operand) => write!(f, "not({operand})"),
+        }
+    }
+}
+
+struct CfgParser<'s> {
+    rest: &'s str,
+}
+
+impl<'s> CfgParser<'s> {
+    fn predicate(&mut self) -> Option<Cfg> {
+        let name = self.ident()?;
+        match name {
+            "any" | "all" => {
+                self.punct('(')?;
+                let mut operands = Vec::new();
+                while !self.peek(')') {
+                    operands.push(self.predicate()?);
+                    if !self.peek(')') {
+                        self.punct(',')?;
+                    }
+                }
+                self.punct(')')?;
+                Some(if name == "any" {
+                    Cfg::Any(operands)
+                } else {
+                    Cfg::All(operands)
+                })
+            }
+            "not" => {
+                self.punct('(')?;
+                let operand = self.predicate()?;
+                self.punct(')')?;
+                Some(Cfg::Not(Box::new(operand)))
+            }
+            _ if self.peek('=') => {
+                self.punct('=')?;
+                let value = self.string()?;
+                Some(Cfg::KeyValue(name.to_owned(), value.to_owned()))
+            }
+            _ => Some(Cfg::Name(name.to_owned())),
+        }
+    }
+
+    fn ident(&mut self) -> Option<&'s str> {
+        self.rest = self.rest.trim_start();
+        let end = self
+            .rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(self.rest.len());
+        let is_ident = self
+            .rest
+            .chars()
+            .next()
+            .map(|c| c.is_alphabetic() || c == '_')
+            .unwrap_or(false);
+        if !is_ident {
+            return None;
+        }
+        let (ident, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Some(ident)
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Option<()> {
+        (self.ident()? == keyword).then_some(())
+    }
+
+    fn string(&mut self) -> Option<&'s str> {
+        self.punct('"')?;
+        let end = self.rest.find(['"', '\\'])?;
+        let (value, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        self.punct('"')?;
+        Some(value)
+    }
+
+    fn peek(&self, c: char) -> bool {
+        self.rest.trim_start().starts_with(c)
+    }
+
+    fn punct(&mut self, c: char) -> Option<()> {
+        self.rest = self.rest.trim_start().strip_prefix(c)?;
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use snapbox::IntoData;
+    use snapbox::assert_data_eq;
+    use snapbox::str;
+
+    #[track_caller]
+    fn valid(input: &str, sort: bool, expected: impl IntoData) {
+        let mut tokens = crate::toml::TomlTokens::parse(input);
+        super::normalize_target_cfgs(&mut tokens, sort, &[]);
+        let actual = tokens.to_string();
+
+        assert_data_eq!(&actual, expected);
+
+        let (_, errors) = toml::de::DeTable::parse_recoverable(&actual);
+        if !errors.is_empty() {
+            use std::fmt::Write as _;
+            let mut result = String::new();
+            writeln!(&mut result, "---").unwrap();
+            for error in errors {
+                writeln!(&mut result, "{error}").unwrap();
+                writeln!(&mut result, "---").unwrap();
+            }
+            panic!("failed to parse\n---\n{actual}\n{result}");
+        }
+    }
+
+    #[test]
+    fn empty() {
+        valid("", false, str![]);
+    }
+
+    #[test]
+    fn reprint() {
+        valid(
+            r#"
+[target.'cfg(any(unix,windows))'.dependencies]
+[target."cfg(target_os = \"linux\")".dev-dependencies]
+[target.'cfg( all( not(windows) , target_pointer_width="64", ) )'.build-dependencies]
+[target.x86_64-pc-windows-gnu.dependencies]
+[target.'cfg(unix'.dependencies]
+"#,
+            false,
+            str![[r#"
+
+[target.'cfg(any(unix, windows))'.dependencies]
+[target.'cfg(target_os = "linux")'.dev-dependencies]
+[target.'cfg(all(not(windows), target_pointer_width = "64"))'.build-dependencies]
+[target.x86_64-pc-windows-gnu.dependencies]
+[target.'cfg(unix'.dependencies]
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn sort() {
+        valid(
+            r#"
+[target.'cfg(any(windows, all(unix, target_os = "linux"), any(b, a)))'.dependencies]
+"#,
+            true,
+            str![[r#"
+
+[target.'cfg(any(all(target_os = "linux", unix), any(a, b), windows))'.dependencies]
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn merge() {
+        valid(
+            r#"
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+
+[dependencies]
+serde = "1"
+
+# Needed for signals
+[target."cfg( unix )".dependencies] # on unix
+nix = "0.29"
+
+[target.'cfg(any(windows, unix))'.dependencies]
+a = "1"
+[target.'cfg(any(unix, windows))'.dependencies]
+b = "1"
+"#,
+            true,
+            str![[r#"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+# Needed for signals
+# on unix
+nix = "0.29"
+
+[dependencies]
+serde = "1"
+
+[target.'cfg(any(unix, windows))'.dependencies]
+a = "1"
+b = "1"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn merge_comment_blocks() {
+        valid(
+            r#"
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+
+[dependencies]
+serde = "1"
+
+# Needed for signals,
+# which only unix has
+[target."cfg( unix )".dependencies]
+nix = "0.29"
+"#,
+            false,
+            str![[r#"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+# Needed for signals,
+# which only unix has
+nix = "0.29"
+
+[dependencies]
+serde = "1"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_conflicting() {
+        valid(
+            r#"
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+
+[target."cfg( unix )".dependencies]
+libc = "0.2"
+
+[target.'cfg(windows)'.dependencies]
+winapi = "0.3"
+
+[target."cfg( windows )".dependencies.winapi]
+version = "0.3"
+"#,
+            false,
+            str![[r#"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+
+[target."cfg( unix )".dependencies]
+libc = "0.2"
+
+[target.'cfg(windows)'.dependencies]
+winapi = "0.3"
+
+[target."cfg( windows )".dependencies.winapi]
+version = "0.3"
+
+"#]],
+        );
+    }
+}
@@ -29,8 +29,7 @@ pub fn trim_trailing_spaces(tokens: &mut crate::toml::TomlTokens<'_>) {
         tokens.tokens[i].raw = match std::mem::take(&mut tokens.tokens[i].raw) {
             Cow::Borrowed(s) => Cow::Borrowed(s.trim_end()),
             Cow::Owned(mut s) => {
-                let trimmed = s.trim_end();
-                s.replace_range(0..trimmed.len(), "");
+                s.truncate(s.trim_end().len());
                 Cow::Owned(s)
             }
         }
@@ -1,13 +1,15 @@
 use std::collections::HashSet;
 
+use super::skip_tables::SkipTables;
 use crate::toml::Table;
 use crate::toml::TokenKind;
 use crate::toml::TomlToken;
 use crate::toml::TomlTokens;
 
 #[tracing::instrument]
-pub fn remove_unused_parent_tables(tokens: &mut TomlTokens<'_>) {
+pub fn remove_unused_parent_tables(tokens: &mut TomlTokens<'_>, skip_tables: &[String]) {
     let tables = Table::new(tokens);
+    let skip = SkipTables::new(skip_tables);
 
     if tables.is_empty() {
         return;
@@ -16,7 +18,7 @@ pub fn remove_unused_parent_tables(tokens: &mut TomlTokens<'_>) {
     let parent_names = find_parent_names(&tables);
 
     for table in tables.iter().rev() {
-        if should_remove(table, tokens, &parent_names) {
+        if !skip.contains(table.name()) && should_remove(table, tokens, &parent_names) {
             for i in table.span() {
                 tokens.tokens[i] = TomlToken::EMPTY;
             }
@@ -80,7 +82,7 @@ mod test {
     #[track_caller]
     fn valid(input: &str, expected: impl IntoData) {
         let mut tokens = crate::toml::TomlTokens::parse(input);
-        super::remove_unused_parent_tables(&mut tokens);
+        super::remove_unused_parent_tables(&mut tokens, &[]);
         let actual = tokens.to_string();
 
         assert_data_eq!(&actual, expected);
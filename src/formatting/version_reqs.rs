@@ -0,0 +1,213 @@
+use super::manifest::is_dependency_subtable;
+use super::manifest::is_dependency_table;
+use crate::toml::Entry;
+use crate::toml::Table;
+use crate::toml::TokenKind;
+use crate::toml::TomlTokens;
+
+/// Normalizes the version requirements of dependencies.
+///
+/// - A redundant `^` is dropped, like `^1.0` to `1.0`
+/// - Operators have no space after them, like `>= 1` to `>=1`
+/// - Comparators are separated by `, `
+///
+/// Requirements are only rewritten when the result matches the same versions.
+/// Requirements that don't parse are left alone.
+#[tracing::instrument]
+pub fn normalize_version_reqs(tokens: &mut TomlTokens<'_>) {
+    let tables = Table::new(tokens);
+    for table in &tables {
+        if table.is_array_table() {
+            continue;
+        }
+
+        let is_dependencies = is_dependency_table(table.name());
+        let is_dependency = is_dependency_subtable(table.name());
+        for entry in Entry::parse(tokens, table.body_span()) {
+            let value = entry.value_span().start;
+            match entry.key() {
+                [_] if is_dependencies => match tokens.tokens[value].kind {
+                    TokenKind::Scalar => normalize_version_req(tokens, value),
+                    TokenKind::InlineTableOpen => {
+                        let version = Entry::parse_inline(tokens, value)
+                            .into_iter()
+                            .find(|e| e.key() == ["version"]);
+                        if let Some(version) = version {
+                            normalize_version_req(tokens, version.value_span().start);
+                        }
+                    }
+                    _ => {}
+                },
+                [_, version] if is_dependencies && version == "version" => {
+                    normalize_version_req(tokens, value);
+                }
+                [version] if is_dependency && version == "version" => {
+                    normalize_version_req(tokens, value);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn normalize_version_req(tokens: &mut TomlTokens<'_>, i: usize) {
+    let token = &mut tokens.tokens[i];
+    if token.kind != TokenKind::Scalar {
+        return;
+    }
+    let Some(req) = token.decoded.as_deref() else {
+        return;
+    };
+    let Some(normalized) = normalized(req) else {
+        return;
+    };
+    if normalized != req {
+        token.set_string(&normalized);
+    }
+}
+
+fn normalized(req: &str) -> Option<String> {
+    let parsed = semver::VersionReq::parse(req).ok()?;
+    let normalized = parsed
+        .comparators
+        .iter()
+        .map(|c| {
+            let c = c.to_string();
+            // Cargo treats a bare version like `^`
+            c.strip_prefix('^').map(str::to_owned).unwrap_or(c)
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let normalized = if parsed.comparators.is_empty() {
+        parsed.to_string()
+    } else {
+        normalized
+    };
+
+    // Never change which versions match
+    let reparsed = semver::VersionReq::parse(&normalized).ok()?;
+    (reparsed == parsed).then_some(normalized)
+}
+
+#[cfg(test)]
+mod test {
+    use snapbox::IntoData;
+    use snapbox::assert_data_eq;
+    use snapbox::str;
+
+    #[track_caller]
+    fn valid(input: &str, expected: impl IntoData) {
+        let mut tokens = crate::toml::TomlTokens::parse(input);
+        super::normalize_version_reqs(&mut tokens);
+        let actual = tokens.to_string();
+
+        assert_data_eq!(&actual, expected);
+
+        let (_, errors) = toml::de::DeTable::parse_recoverable(&actual);
+        if !errors.is_empty() {
+            use std::fmt::Write as _;
+            let mut result = String::new();
+            writeln!(&mut result, "---").unwrap();
+            for error in errors {
+                writeln!(&mut result, "{error}").unwrap();
+                writeln!(&mut result, "---").unwrap();
+            }
+            panic!("failed to parse\n---\n{actual}\n{result}");
+        }
+    }
+
+    #[test]
+    fn empty() {
+        valid("", str![]);
+    }
+
+    #[test]
+    fn requirements() {
+        valid(
+            r#"
+[dependencies]
+caret = "^1.0"
+bare = "1.0"
+exact = "= 1.2.3"
+range = ">=1 ,<2"
+tilde = "~1"
+spaced = "  ^ 1.2  "
+multiple = "^1.2, < 1.5"
+wildcard = "1.*"
+star = "*"
+prerelease = "^1.0.0-alpha.1"
+literal = '>= 0.3'
+"#,
+            str![[r#"
+
+[dependencies]
+caret = "1.0"
+bare = "1.0"
+exact = "=1.2.3"
+range = ">=1, <2"
+tilde = "~1"
+spaced = "1.2"
+multiple = "1.2, <1.5"
+wildcard = "1.*"
+star = "*"
+prerelease = "1.0.0-alpha.1"
+literal = '>=0.3'
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn dependency_forms() {
+        valid(
+            r#"
+[workspace.dependencies]
+serde = { version = "^1.0", features = ["derive"] }
+
+[target.'cfg(unix)'.dev-dependencies]
+libc.version = ">= 0.2"
+
+[build-dependencies.cc]
+version = "^ 1"
+"#,
+            str![[r#"
+
+[workspace.dependencies]
+serde = { version = "1.0", features = ["derive"] }
+
+[target.'cfg(unix)'.dev-dependencies]
+libc.version = ">=0.2"
+
+[build-dependencies.cc]
+version = "1"
+
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_unparseable_and_other_values() {
+        valid(
+            r#"
+[package]
+version = "^1.0"
+
+[dependencies]
+bad = "^1.0 || ^2.0"
+git = { git = "https://github.com/foo/bar", branch = "^1.0" }
+local = { path = "^1.0" }
+"#,
+            str![[r#"
+
+[package]
+version = "^1.0"
+
+[dependencies]
+bad = "^1.0 || ^2.0"
+git = { git = "https://github.com/foo/bar", branch = "^1.0" }
+local = { path = "^1.0" }
+
+"#]],
+        );
+    }
+}
@@ -0,0 +1,563 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::ops::Range;
+
+use super::manifest::is_dependency_subtable;
+use super::manifest::is_dependency_table;
+use super::skip_tables::SkipTables;
+use crate::toml::Entry;
+use crate::toml::Table;
+use crate::toml::TokenKind;
+use crate::toml::TomlToken;
+use crate::toml::TomlTokens;
+
+/// Package fields that members may inherit from `[workspace.package]`
+const PACKAGE_FIELDS: [&str; 4] = ["edition", "license", "repository", "rust-version"];
+
+/// Dependency keys that may sit next to `workspace = true`
+const MEMBER_DEPENDENCY_KEYS: [&str; 3] = ["version", "features", "optional"];
+
+/// What a workspace root offers its members to inherit
+#[derive(Clone, Debug, Default)]
+pub struct WorkspaceFields {
+    /// Registry dependencies, by name
+    dependencies: BTreeMap<String, WorkspaceDependency>,
+    package: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug)]
+struct WorkspaceDependency {
+    version: semver::VersionReq,
+    /// Features members get on top of their own
+    features: Vec<String>,
+}
+
+impl WorkspaceFields {
+    pub fn parse(workspace_manifest: &str) -> Result<Self, io::Error> {
+        let manifest = workspace_manifest
+            .parse::<::toml::Table>()
+            .map_err(io::Error::other)?;
+        let Some(workspace) = manifest.get("workspace").and_then(|w| w.as_table()) else {
+            return Ok(Self::default());
+        };
+
+        let mut fields = Self::default();
+        let dependencies = workspace.get("dependencies").and_then(|d| d.as_table());
+        for (name, dependency) in dependencies.into_iter().flatten() {
+            if let Some(dependency) = inheritable_dependency(dependency) {
+                fields.dependencies.insert(name.clone(), dependency);
+            }
+        }
+        let package = workspace.get("package").and_then(|p| p.as_table());
+        for (key, value) in package.into_iter().flatten() {
+            if let (true, Some(value)) = (PACKAGE_FIELDS.contains(&key.as_str()), value.as_str()) {
+                fields.package.insert(key.clone(), value.to_owned());
+            }
+        }
+        Ok(fields)
+    }
+}
+
+/// A `[workspace.dependencies]` entry that members can inherit as-is
+///
+/// Entries from other sources, renamed or without default features would change what
+/// members depend on.
+fn inheritable_dependency(dependency: &::toml::Value) -> Option<WorkspaceDependency> {
+    let (version, features) = match dependency {
+        ::toml::Value::String(version) => (version.as_str(), Vec::new()),
+        ::toml::Value::Table(table) => {
+            const OTHER_SOURCES: [&str; 5] =
+                ["path", "git", "registry", "registry-index", "package"];
+            let is_default_features = ["default-features", "default_features"]
+                .iter()
+                .filter_map(|key| table.get(*key))
+                .all(|value| value.as_bool() == Some(true));
+            if OTHER_SOURCES.iter().any(|key| table.contains_key(*key)) || !is_default_features {
+                return None;
+            }
+            let features = match table.get("features") {
+                Some(features) => features
+                    .as_array()?
+                    .iter()
+                    .map(|f| f.as_str().map(str::to_owned))
+                    .collect::<Option<Vec<_>>>()?,
+                None => Vec::new(),
+            };
+            (table.get("version")?.as_str()?, features)
+        }
+        _ => return None,
+    };
+    let version = semver::VersionReq::parse(version).ok()?;
+    Some(WorkspaceDependency { version, features })
+}
+
+/// Rewrites dependencies and package fields to inherit from the workspace when they match
+///
+/// - A dependency inherits when the workspace's requirement is within its own, like
+///   `serde = "1.0"` with `serde = "1.0.200"` in `[workspace.dependencies]`, becoming
+///   `serde = { workspace = true }`, as long as it already lists the workspace's features
+/// - `edition`, `license`, `repository` and `rust-version` inherit when equal to the
+///   value in `[workspace.package]`, becoming `edition.workspace = true`
+///
+/// Fields in `skip_tables` are left alone.  Each rewrite is described in `changes`.
+#[tracing::instrument(skip(workspace, changes))]
+pub fn inherit_workspace_fields(
+    tokens: &mut TomlTokens<'_>,
+    workspace: &WorkspaceFields,
+    skip_tables: &[String],
+    changes: &mut Vec<String>,
+) {
+    let skip = SkipTables::new(skip_tables);
+    let snippet = Snippet::new();
+    let mut edits = Vec::new();
+    for table in Table::new(tokens) {
+        if table.is_array_table() {
+            continue;
+        }
+        let name = table.name();
+        let entries = Entry::parse(tokens, table.body_span());
+        if name.first().map(|n| n == "workspace").unwrap_or(false) || skip.contains(name) {
+            continue;
+        }
+
+        if name == ["package"] || name == ["project"] {
+            for entry in &entries {
+                let [key] = entry.key() else {
+                    continue;
+                };
+                let Some(expected) = workspace.package.get(key) else {
+                    continue;
+                };
+                if skip.overlaps(&[name, entry.key()].concat()) {
+                    continue;
+                }
+                if string(tokens, entry) == Some(expected.as_str()) {
+                    let key_end = entry.key_span().end;
+                    edits.push((key_end..key_end, snippet.dotted_workspace_key()));
+                    edits.push((entry.value_span(), vec![snippet.true_value()]));
+                    changes.push(inherited(&name.join("."), key, "package"));
+                }
+            }
+        } else if is_dependency_table(name) {
+            let mut dotted = BTreeMap::<&str, Vec<&Entry>>::new();
+            for entry in &entries {
+                match entry.key() {
+                    [dependency] if !skip.overlaps(&[name, entry.key()].concat()) => {
+                        let value = entry.value_span().start;
+                        let version = match tokens.tokens[value].kind {
+                            TokenKind::Scalar => {
+                                string(tokens, entry).map(|v| (v, None, Vec::new()))
+                            }
+                            TokenKind::InlineTableOpen => {
+                                let inline = Entry::parse_inline(tokens, value);
+                                inline_version(tokens, &inline)
+                                    .map(|(v, e)| (v, Some(e.clone()), features(tokens, &inline)))
+                            }
+                            _ => None,
+                        };
+                        let Some((version, version_entry, features)) = version else {
+                            continue;
+                        };
+                        if !inherits(workspace, dependency, version, &features) {
+                            continue;
+                        }
+                        match version_entry {
+                            Some(version_entry) => {
+                                edits.extend(snippet.workspace_entry(&version_entry));
+                            }
+                            None => edits.push((entry.value_span(), snippet.workspace_table())),
+                        }
+                        changes.push(inherited(&name.join("."), dependency, "dependencies"));
+                    }
+                    [dependency, _] if !skip.overlaps(&[name, &entry.key()[..1]].concat()) => {
+                        dotted.entry(dependency).or_default().push(entry);
+                    }
+                    _ => {}
+                }
+            }
+            for (dependency, entries) in dotted {
+                let Some((version, version_entry)) = inline_version(tokens, entries.clone()) else {
+                    continue;
+                };
+                if inherits(workspace, dependency, version, &features(tokens, entries)) {
+                    edits.extend(snippet.workspace_entry(version_entry));
+                    changes.push(inherited(&name.join("."), dependency, "dependencies"));
+                }
+            }
+        } else if is_dependency_subtable(name) {
+            let (dependency, parent) = name.split_last().expect("subtables are nested");
+            let Some((version, version_entry)) = inline_version(tokens, &entries) else {
+                continue;
+            };
+            if inherits(workspace, dependency, version, &features(tokens, &entries)) {
+                edits.extend(snippet.workspace_entry(version_entry));
+                changes.push(inherited(&parent.join("."), dependency, "dependencies"));
+            }
+        }
+    }
+
+    edits.sort_by_key(|(span, _)| span.start);
+    for (span, replacement) in edits.into_iter().rev() {
+        tokens.tokens.splice(span, replacement);
+    }
+}
+
+/// The `version` of a dependency's entries, as long as nothing else rules out inheriting
+fn inline_version<'t, 'e>(
+    tokens: &'t TomlTokens<'_>,
+    entries: impl IntoIterator<Item = &'e Entry>,
+) -> Option<(&'t str, &'e Entry)> {
+    let mut version = None;
+    for entry in entries {
+        let key = entry.key().last()?;
+        if !MEMBER_DEPENDENCY_KEYS.contains(&key.as_str()) {
+            return None;
+        }
+        if key == "version" {
+            version = Some((string(tokens, entry)?, entry));
+        }
+    }
+    version
+}
+
+/// The `features` of a dependency's entries
+fn features<'t, 'e>(
+    tokens: &'t TomlTokens<'_>,
+    entries: impl IntoIterator<Item = &'e Entry>,
+) -> Vec<&'t str> {
+    entries
+        .into_iter()
+        .filter(|e| e.key().last().map(|k| k == "features").unwrap_or(false))
+        .flat_map(|e| tokens.tokens[e.value_span()].iter())
+        .filter(|t| t.kind == TokenKind::Scalar)
+        .filter_map(|t| t.decoded.as_deref())
+        .collect()
+}
+
+/// Whether the workspace's requirement for `dependency` only matches versions `member` does,
+/// and it enables no features beyond `member_features`
+///
+/// Only caret requirements are compared, where a shared lower bound implies a shared upper
+/// bound.
+fn inherits(
+    workspace: &WorkspaceFields,
+    dependency: &str,
+    member: &str,
+    member_features: &[&str],
+) -> bool {
+    let Some(workspace) = workspace.dependencies.get(dependency) else {
+        return false;
+    };
+    // Inheriting adds the workspace's features to the member's
+    if !workspace
+        .features
+        .iter()
+        .all(|f| member_features.contains(&f.as_str()))
+    {
+        return false;
+    }
+    let Ok(member) = semver::VersionReq::parse(member) else {
+        return false;
+    };
+    match (
+        workspace.version.comparators.as_slice(),
+        member.comparators.as_slice(),
+    ) {
+        ([workspace], [member_comparator])
+            if workspace.op == semver::Op::Caret && member_comparator.op == semver::Op::Caret =>
+        {
+            let mut lower = semver::Version::new(
+                workspace.major,
+                workspace.minor.unwrap_or(0),
+                workspace.patch.unwrap_or(0),
+            );
+            lower.pre = workspace.pre.clone();
+            member.matches(&lower)
+        }
+        _ => false,
+    }
+}
+
+fn inherited(table: &str, key: &str, workspace_table: &str) -> String {
+    format!("inheriting `{table}.{key}` from `workspace.{workspace_table}.{key}`")
+}
+
+fn string<'t>(tokens: &'t TomlTokens<'_>, entry: &Entry) -> Option<&'t str> {
+    match &tokens.tokens[entry.value_span()] {
+        [value] if value.kind == TokenKind::Scalar => value.decoded.as_deref(),
+        _ => None,
+    }
+}
+
+/// Tokens for spelling out `workspace = true`
+struct Snippet {
+    tokens: Vec<TomlToken<'static>>,
+}
+
+impl Snippet {
+    fn new() -> Self {
+        Self {
+            tokens: TomlTokens::parse_snippet("key.workspace = { workspace = true }"),
+        }
+    }
+
+    fn find(&self, kind: TokenKind) -> TomlToken<'static> {
+        self.tokens
+            .iter()
+            .find(|t| t.kind == kind)
+            .expect("snippet is complete")
+            .clone()
+    }
+
+    fn workspace_key(&self) -> TomlToken<'static> {
+        self.tokens
+            .iter()
+            .find(|t| t.kind == TokenKind::SimpleKey && t.raw == "workspace")
+            .expect("snippet is complete")
+            .clone()
+    }
+
+    fn true_value(&self) -> TomlToken<'static> {
+        self.find(TokenKind::Scalar)
+    }
+
+    /// `.workspace`, to follow a key
+    fn dotted_workspace_key(&self) -> Vec<TomlToken<'static>> {
+        vec![self.find(TokenKind::KeySep), self.workspace_key()]
+    }
+
+    /// `{ workspace = true }`
+    fn workspace_table(&self) -> Vec<TomlToken<'static>> {
+        let open = self
+            .tokens
+            .iter()
+            .position(|t| t.kind == TokenKind::InlineTableOpen)
+            .expect("snippet is complete");
+        let close = self
+            .tokens
+            .iter()
+            .position(|t| t.kind == TokenKind::InlineTableClose)
+            .expect("snippet is complete");
+        self.tokens[open..=close].to_vec()
+    }
+
+    /// Edits turning a `version = "..."` entry into `workspace = true`
+    fn workspace_entry(&self, version: &Entry) -> [(Range<usize>, Vec<TomlToken<'static>>); 2] {
+        let key = version.key_span().end - 1;
+        [
+            (key..key + 1, vec![self.workspace_key()]),
+            (version.value_span(), vec![self.true_value()]),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use snapbox::IntoData;
+    use snapbox::assert_data_eq;
+    use snapbox::str;
+
+    const WORKSPACE: &str = r#"
+[workspace.package]
+edition = "2021"
+license = "MIT OR Apache-2.0"
+repository = "https://github.com/foo/bar"
+rust-version = "1.85"
+
+[workspace.dependencies]
+serde = { version = "1.0.200", features = ["derive", "rc"] }
+toml = "0.9"
+anyhow = "1"
+tokio = { version = "1", features = ["full"] }
+libc = { version = "0.2", default-features = false }
+local = { path = "crates/local", version = "0.1" }
+"#;
+
+    #[track_caller]
+    fn valid(input: &str, expected: impl IntoData, expected_changes: impl IntoData) {
+        let workspace = super::WorkspaceFields::parse(WORKSPACE).unwrap();
+        let mut tokens = crate::toml::TomlTokens::parse(input);
+        let mut changes = Vec::new();
+        super::inherit_workspace_fields(&mut tokens, &workspace, &[], &mut changes);
+        let actual = tokens.to_string();
+
+        assert_data_eq!(&actual, expected);
+        assert_data_eq!(changes.join("\n"), expected_changes);
+
+        let (_, errors) = toml::de::DeTable::parse_recoverable(&actual);
+        if !errors.is_empty() {
+            use std::fmt::Write as _;
+            let mut result = String::new();
+            writeln!(&mut result, "---").unwrap();
+            for error in errors {
+                writeln!(&mut result, "{error}").unwrap();
+                writeln!(&mut result, "---").unwrap();
+            }
+            panic!("failed to parse\n---\n{actual}\n{result}");
+        }
+    }
+
+    #[test]
+    fn empty() {
+        valid("", str![], str![]);
+    }
+
+    #[test]
+    fn package() {
+        valid(
+            r#"
+[package]
+name = "foo"
+edition = "2021"
+license = "MIT" # relicensed
+repository = "https://github.com/foo/bar"
+rust-version.workspace = true
+"#,
+            str![[r#"
+
+[package]
+name = "foo"
+edition.workspace = true
+license = "MIT" # relicensed
+repository.workspace = true
+rust-version.workspace = true
+
+"#]],
+            str![[r#"
+inheriting `package.edition` from `workspace.package.edition`
+inheriting `package.repository` from `workspace.package.repository`
+"#]],
+        );
+    }
+
+    #[test]
+    fn dependencies() {
+        valid(
+            r#"
+[dependencies]
+serde = { version = "1.0", features = ["rc", "derive"] }
+toml = "0.9.8"
+anyhow = "1.0"
+libc = "0.2"
+local = "0.1"
+other = "1"
+
+[dev-dependencies]
+serde.version = "1"
+serde.features = ["derive", "rc"]
+serde.optional = true
+
+[target.'cfg(unix)'.build-dependencies.anyhow]
+version = "1"
+"#,
+            str![[r#"
+
+[dependencies]
+serde = { workspace = true, features = ["rc", "derive"] }
+toml = "0.9.8"
+anyhow = { workspace = true }
+libc = "0.2"
+local = "0.1"
+other = "1"
+
+[dev-dependencies]
+serde.workspace = true
+serde.features = ["derive", "rc"]
+serde.optional = true
+
+[target.'cfg(unix)'.build-dependencies.anyhow]
+workspace = true
+
+"#]],
+            str![[r#"
+inheriting `dependencies.serde` from `workspace.dependencies.serde`
+inheriting `dependencies.anyhow` from `workspace.dependencies.anyhow`
+inheriting `dev-dependencies.serde` from `workspace.dependencies.serde`
+inheriting `target.cfg(unix).build-dependencies.anyhow` from `workspace.dependencies.anyhow`
+"#]],
+        );
+    }
+
+    #[test]
+    fn keep_incompatible_dependencies() {
+        valid(
+            r#"
+[dependencies]
+serde = { version = "1.0", path = "../serde" }
+toml = "~0.9"
+anyhow = { version = "1", default-features = false }
+"#,
+            str![[r#"
+
+[dependencies]
+serde = { version = "1.0", path = "../serde" }
+toml = "~0.9"
+anyhow = { version = "1", default-features = false }
+
+"#]],
+            str![],
+        );
+    }
+
+    #[test]
+    fn keep_dependencies_missing_workspace_features() {
+        valid(
+            r#"
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+tokio = { version = "1", optional = true }
+
+[dev-dependencies]
+serde.version = "1"
+
+[build-dependencies.tokio]
+version = "1"
+features = ["rt"]
+"#,
+            str![[r#"
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+tokio = { version = "1", optional = true }
+
+[dev-dependencies]
+serde.version = "1"
+
+[build-dependencies.tokio]
+version = "1"
+features = ["rt"]
+
+"#]],
+            str![],
+        );
+    }
+
+    #[test]
+    fn keep_dependencies_without_workspace_default_features() {
+        valid(
+            r#"
+[dependencies]
+libc = { version = "0.2", default-features = false }
+
+[dev-dependencies]
+libc.version = "0.2"
+
+[build-dependencies.libc]
+version = "0.2"
+"#,
+            str![[r#"
+
+[dependencies]
+libc = { version = "0.2", default-features = false }
+
+[dev-dependencies]
+libc.version = "0.2"
+
+[build-dependencies.libc]
+version = "0.2"
+
+"#]],
+            str![],
+        );
+    }
+}
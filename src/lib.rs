@@ -8,15 +8,45 @@ pub mod config;
 pub mod formatting;
 pub mod toml;
 
-pub fn fmt_manifest(raw_input_text: &str, config: config::Config) -> Option<String> {
+/// Formats `raw_input_text`, returning `None` when formatting is disabled for it
+///
+/// Fails if `config` requires a different version of cargo-cargofmt.
+pub fn fmt_manifest(
+    raw_input_text: &str,
+    config: config::Config,
+) -> Result<Option<String>, std::io::Error> {
+    fmt_manifest_with_warnings(
+        raw_input_text,
+        None,
+        config,
+        &mut Vec::new(),
+        &mut Vec::new(),
+    )
+}
+
+/// Formats `raw_input_text` like [`fmt_manifest`], collecting what couldn't be formatted
+/// into `warnings`
+///
+/// With [`config::Config::inherit_workspace_fields`], fields shared with
+/// `workspace_manifest` are inherited from it, each described in `changes`.  Fails if
+/// `workspace_manifest` isn't valid TOML.
+pub fn fmt_manifest_with_warnings(
+    raw_input_text: &str,
+    workspace_manifest: Option<&str>,
+    config: config::Config,
+    warnings: &mut Vec<String>,
+    changes: &mut Vec<String>,
+) -> Result<Option<String>, std::io::Error> {
+    config.check_required_version()?;
+
     if config.disable_all_formatting {
-        return None;
+        return Ok(None);
     }
 
     if !config.format_generated_files
         && formatting::is_generated_file(raw_input_text, config.generated_marker_line_search_limit)
     {
-        return None;
+        return Ok(None);
     }
 
     let mut input = raw_input_text.to_owned();
@@ -30,12 +60,84 @@ pub fn fmt_manifest(raw_input_text: &str, config: config::Config) -> Option<Stri
 
     let mut tokens = toml::TomlTokens::parse(&input);
 
-    formatting::normalize_strings(&mut tokens);
-    formatting::normalize_datetime_separators(&mut tokens);
-    formatting::remove_unused_parent_tables(&mut tokens);
+    if let (true, Some(workspace_manifest)) = (config.inherit_workspace_fields, workspace_manifest)
+    {
+        let workspace = formatting::WorkspaceFields::parse(workspace_manifest)?;
+        formatting::inherit_workspace_fields(&mut tokens, &workspace, &config.skip_tables, changes);
+    }
+
+    if config.normalize_strings {
+        formatting::normalize_strings(&mut tokens);
+    }
+    if config.normalize_datetime_separators() {
+        formatting::normalize_datetime_separators(&mut tokens);
+    }
+    formatting::normalize_dotted_keys(&mut tokens, config.dotted_keys, &config.skip_tables);
+    if config.rewrite_deprecated_keys {
+        formatting::rewrite_deprecated_keys(&mut tokens, &config.skip_tables, warnings);
+    }
+    if config.normalize_target_cfgs {
+        formatting::normalize_target_cfgs(
+            &mut tokens,
+            config.sort_cfg_predicates,
+            &config.skip_tables,
+        );
+    }
+    if config.reorder_tables {
+        formatting::reorder_tables(&mut tokens, &config.table_order);
+    }
+    if config.convert_dependency_tables {
+        formatting::convert_dependency_tables(
+            &mut tokens,
+            config.inline_table_width(),
+            &config.skip_tables,
+        );
+    }
+    // After converting dependency tables, which can empty or add parent tables
+    if config.remove_unused_parent_tables() {
+        formatting::remove_unused_parent_tables(&mut tokens, &config.skip_tables);
+    }
+    if config.group_subtables {
+        formatting::group_subtables(&mut tokens);
+    }
+    if config.collapse_version_only_dependencies {
+        formatting::collapse_version_only_dependencies(&mut tokens);
+    }
+    if config.reorder_dependency_keys {
+        formatting::reorder_dependency_keys(&mut tokens, &config.dependency_key_order);
+    }
+    if config.normalize_features {
+        formatting::normalize_features(&mut tokens, config.sort_feature_keys);
+    }
+    if config.sort_lints {
+        formatting::sort_lints(&mut tokens);
+    }
+    if config.normalize_version_reqs {
+        formatting::normalize_version_reqs(&mut tokens);
+    }
+    if config.normalize_paths {
+        formatting::normalize_paths(&mut tokens);
+    }
+    if config.normalize_licenses {
+        formatting::normalize_licenses(&mut tokens, warnings);
+    }
+    if config.remove_redundant_defaults {
+        formatting::remove_redundant_defaults(&mut tokens, &config.skip_tables);
+    }
+    if config.sort_string_arrays {
+        formatting::sort_string_arrays(&mut tokens, &config.sorted_arrays);
+    }
+    if config.reorder_package_keys {
+        formatting::reorder_package_keys(&mut tokens, &config.package_key_order);
+    }
+    if config.sort_dependencies {
+        formatting::sort_dependencies(&mut tokens);
+    }
     formatting::trim_trailing_spaces(&mut tokens);
     formatting::normalize_space_separators(&mut tokens);
-    formatting::reflow_arrays(&mut tokens, config.array_width(), config.tab_spaces);
+    if config.reflow_arrays() {
+        formatting::reflow_arrays(&mut tokens, config.array_width(), config.tab_spaces);
+    }
     formatting::constrain_blank_lines(
         &mut tokens,
         config.blank_lines_lower_bound,
@@ -44,11 +146,16 @@ pub fn fmt_manifest(raw_input_text: &str, config: config::Config) -> Option<Stri
     formatting::adjust_trailing_comma(&mut tokens, config.trailing_comma);
     formatting::normalize_indent(&mut tokens, config.hard_tabs, config.tab_spaces);
 
+    if !config.skip_tables.is_empty() {
+        let original = toml::TomlTokens::parse(&input);
+        formatting::restore_skipped_tables(&mut tokens, &original, &config.skip_tables, warnings);
+    }
+
     let mut formatted = tokens.to_string();
 
     formatting::apply_newline_style(config.newline_style, &mut formatted, raw_input_text);
 
-    Some(formatted)
+    Ok(Some(formatted))
 }
 
 #[doc = include_str!("../README.md")]
@@ -0,0 +1,240 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use super::TokenKind;
+use super::TomlToken;
+use super::TomlTokens;
+
+/// An array of scalars, along with its comments
+#[derive(Clone, Debug)]
+pub struct Array {
+    /// From `[` through `]`
+    span: Range<usize>,
+    elements: Vec<ArrayElement>,
+    /// Comment on the same line as `[`
+    open_comment: Option<usize>,
+    /// Comment lines after the last element
+    close_comments: Vec<usize>,
+    is_vertical: bool,
+    has_trailing_comma: bool,
+}
+
+/// A scalar in an [`Array`]
+#[derive(Clone, Debug)]
+pub struct ArrayElement {
+    value: usize,
+    /// Comment lines directly above the value
+    leading_comments: Vec<usize>,
+    /// Comment on the same line, after the value
+    trailing_comment: Option<usize>,
+}
+
+impl Array {
+    /// Parses the array opened at `open`
+    ///
+    /// Returns `None` for arrays containing arrays or inline tables.
+    pub fn parse(tokens: &TomlTokens<'_>, open: usize) -> Option<Self> {
+        let mut elements = Vec::<ArrayElement>::new();
+        let mut open_comment = None;
+        let mut pending_comments = Vec::new();
+        let mut is_vertical = false;
+        let mut has_trailing_comma = false;
+        let mut on_value_line = false;
+
+        for i in open + 1..tokens.len() {
+            match tokens.tokens[i].kind {
+                TokenKind::Whitespace => {}
+                TokenKind::Newline => {
+                    is_vertical = true;
+                    on_value_line = false;
+                }
+                TokenKind::Comment => match elements.last_mut() {
+                    Some(last) if on_value_line => last.trailing_comment = Some(i),
+                    None if !is_vertical => open_comment = Some(i),
+                    _ => pending_comments.push(i),
+                },
+                TokenKind::Scalar => {
+                    elements.push(ArrayElement {
+                        value: i,
+                        leading_comments: std::mem::take(&mut pending_comments),
+                        trailing_comment: None,
+                    });
+                    on_value_line = true;
+                    has_trailing_comma = false;
+                }
+                TokenKind::ValueSep => has_trailing_comma = true,
+                TokenKind::ArrayClose => {
+                    return Some(Self {
+                        span: open..i + 1,
+                        elements,
+                        open_comment,
+                        close_comments: pending_comments,
+                        is_vertical,
+                        has_trailing_comma,
+                    });
+                }
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    pub fn elements(&self) -> &[ArrayElement] {
+        &self.elements
+    }
+
+    /// Rebuilds the array with `elements[order[i]]` at position `i`, keeping its layout
+    ///
+    /// Elements left out of `order` are removed.  Comments move along with their
+    /// element.
+    pub fn rewrite(&self, tokens: &mut TomlTokens<'_>, order: &[usize]) {
+        let token = |i: usize| tokens.tokens[i].clone();
+
+        let mut rewritten = vec![token(self.span.start)];
+        if self.is_vertical {
+            let indent = whitespace(self.indent(tokens));
+            let close_indent = whitespace(self.close_indent(tokens));
+            if let Some(comment) = self.open_comment {
+                rewritten.extend([TomlToken::SPACE, token(comment)]);
+            }
+            rewritten.push(TomlToken::NL);
+            for (position, &i) in order.iter().enumerate() {
+                let element = &self.elements[i];
+                for &comment in &element.leading_comments {
+                    rewritten.extend([indent.clone(), token(comment), TomlToken::NL]);
+                }
+                rewritten.extend([indent.clone(), token(element.value)]);
+                if position + 1 < order.len() || self.has_trailing_comma {
+                    rewritten.push(TomlToken::VAL_SEP);
+                }
+                if let Some(comment) = element.trailing_comment {
+                    rewritten.extend([TomlToken::SPACE, token(comment)]);
+                }
+                rewritten.push(TomlToken::NL);
+            }
+            for &comment in &self.close_comments {
+                rewritten.extend([indent.clone(), token(comment), TomlToken::NL]);
+            }
+            rewritten.push(close_indent);
+        } else {
+            for (position, &i) in order.iter().enumerate() {
+                if 0 < position {
+                    rewritten.extend([TomlToken::VAL_SEP, TomlToken::SPACE]);
+                }
+                rewritten.push(token(self.elements[i].value));
+            }
+            if self.has_trailing_comma && !order.is_empty() {
+                rewritten.push(TomlToken::VAL_SEP);
+            }
+        }
+        rewritten.push(token(self.span.end - 1));
+
+        tokens.tokens.splice(self.span(), rewritten);
+    }
+
+    /// Indentation of the first element or comment line
+    fn indent<'t>(&self, tokens: &'t TomlTokens<'_>) -> &'t str {
+        let first = self
+            .elements
+            .first()
+            .map(|e| e.leading_comments.first().copied().unwrap_or(e.value))
+            .or_else(|| self.close_comments.first().copied());
+        first.and_then(|i| line_indent(tokens, i)).unwrap_or("    ")
+    }
+
+    /// Indentation of `]`
+    fn close_indent<'t>(&self, tokens: &'t TomlTokens<'_>) -> &'t str {
+        line_indent(tokens, self.span.end - 1).unwrap_or("")
+    }
+}
+
+impl ArrayElement {
+    /// Index of the scalar's token
+    pub fn value(&self) -> usize {
+        self.value
+    }
+
+    pub fn has_comments(&self) -> bool {
+        !self.leading_comments.is_empty() || self.trailing_comment.is_some()
+    }
+}
+
+/// The whitespace before `i`, if `i` starts its line
+fn line_indent<'t>(tokens: &'t TomlTokens<'_>, i: usize) -> Option<&'t str> {
+    match i.checked_sub(1).map(|p| &tokens.tokens[p]) {
+        Some(prev) if prev.kind == TokenKind::Newline => Some(""),
+        Some(prev) if prev.kind == TokenKind::Whitespace => {
+            let is_line_start = i < 2 || tokens.tokens[i - 2].kind == TokenKind::Newline;
+            is_line_start.then_some(prev.raw.as_ref())
+        }
+        _ => None,
+    }
+}
+
+fn whitespace(raw: &str) -> TomlToken<'static> {
+    TomlToken {
+        raw: Cow::Owned(raw.to_owned()),
+        ..TomlToken::EMPTY
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[track_caller]
+    fn rewrite(input: &str, order: impl FnOnce(&[ArrayElement]) -> Vec<usize>) -> String {
+        let mut tokens = TomlTokens::parse(input);
+        let open = tokens
+            .tokens
+            .iter()
+            .position(|t| t.kind == TokenKind::ArrayOpen)
+            .unwrap();
+        let array = Array::parse(&tokens, open).unwrap();
+        let order = order(array.elements());
+        array.rewrite(&mut tokens, &order);
+        tokens.to_string()
+    }
+
+    #[test]
+    fn nested_arrays_are_unsupported() {
+        let tokens = TomlTokens::parse("a = [[1], 2]\n");
+        assert!(Array::parse(&tokens, 2).is_none());
+    }
+
+    #[test]
+    fn rewrite_horizontal() {
+        assert_eq!(rewrite("a = [1, 2,3,]\n", |_| vec![2, 0]), "a = [3, 1,]\n");
+        assert_eq!(rewrite("a = [1, 2]\n", |_| vec![]), "a = []\n");
+    }
+
+    #[test]
+    fn rewrite_vertical() {
+        let input = "a = [ # open
+  # one
+  1, # trailing one
+  2,
+  3 # trailing three
+  # close
+]
+";
+        assert_eq!(
+            rewrite(input, |e| {
+                assert!(e[0].has_comments());
+                assert!(!e[1].has_comments());
+                vec![2, 0]
+            }),
+            "a = [ # open
+  3, # trailing three
+  # one
+  1 # trailing one
+  # close
+]
+"
+        );
+    }
+}
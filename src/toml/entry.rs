@@ -0,0 +1,314 @@
+use std::ops::Range;
+
+use super::TokenKind;
+use super::TomlTokens;
+
+/// A key/value pair in a table body, along with its comments
+#[derive(Clone, Debug)]
+pub struct Entry {
+    key: Vec<String>,
+    /// From the first leading comment through the newline ending the entry
+    span: Range<usize>,
+    key_span: Range<usize>,
+    value_span: Range<usize>,
+}
+
+impl Entry {
+    /// Collects the key/value pairs in `span`, usually a [`Table::body_span`][super::Table::body_span]
+    ///
+    /// Comment lines directly above a key/value pair are considered a part of it.
+    #[tracing::instrument(skip(tokens))]
+    pub fn parse(tokens: &TomlTokens<'_>, span: Range<usize>) -> Vec<Self> {
+        let mut entries = Vec::new();
+        let mut leading_comment = None;
+        let mut i = span.start;
+
+        while i < span.end {
+            let line_start = i;
+            while i < span.end && tokens.tokens[i].kind == TokenKind::Whitespace {
+                i += 1;
+            }
+            if span.end <= i {
+                break;
+            }
+
+            match tokens.tokens[i].kind {
+                TokenKind::Newline => {
+                    // Blank lines detach comments from the next entry
+                    leading_comment = None;
+                    i += 1;
+                }
+                TokenKind::Comment => {
+                    leading_comment.get_or_insert(line_start);
+                    i = line_end(tokens, i, span.end);
+                }
+                TokenKind::SimpleKey => {
+                    let (key, key_span, value_span) = parse_key_value(tokens, i, span.end);
+                    let end = line_end(tokens, value_span.end, span.end);
+                    entries.push(Self {
+                        key,
+                        span: leading_comment.take().unwrap_or(line_start)..end,
+                        key_span,
+                        value_span,
+                    });
+                    i = end;
+                }
+                _ => {
+                    leading_comment = None;
+                    i = line_end(tokens, i, span.end);
+                }
+            }
+        }
+
+        entries
+    }
+
+    /// Collects the key/value pairs of the inline table opened at `open`
+    ///
+    /// Spans cover `key = value`, without separators or comments.
+    pub fn parse_inline(tokens: &TomlTokens<'_>, open: usize) -> Vec<Self> {
+        let close = value_end(tokens, open, tokens.len()).saturating_sub(1);
+        let mut entries = Vec::new();
+        let mut i = open + 1;
+        while i < close {
+            if tokens.tokens[i].kind == TokenKind::SimpleKey {
+                let (key, key_span, value_span) = parse_key_value(tokens, i, close);
+                i = value_span.end.max(i + 1);
+                entries.push(Self {
+                    key,
+                    span: key_span.start..value_span.end,
+                    key_span,
+                    value_span,
+                });
+            } else {
+                i += 1;
+            }
+        }
+        entries
+    }
+
+    /// Splits `entries` into runs of adjacent entries
+    ///
+    /// Blank lines and comments that aren't attached to an entry end a run.
+    pub fn runs(entries: &[Self]) -> Vec<&[Self]> {
+        let mut runs = Vec::new();
+        let mut run_start = 0;
+        for i in 1..=entries.len() {
+            let is_adjacent = entries
+                .get(i)
+                .map(|next| entries[i - 1].span.end == next.span.start)
+                .unwrap_or(false);
+            if !is_adjacent {
+                runs.push(&entries[run_start..i]);
+                run_start = i;
+            }
+        }
+        runs
+    }
+
+    /// Stable-sorts `entries` with `compare`, moving comments along
+    ///
+    /// Whatever is between entries, like blank lines, stays in place.
+    pub fn sort_by(
+        tokens: &mut TomlTokens<'_>,
+        entries: &[Self],
+        mut compare: impl FnMut(&Self, &Self) -> std::cmp::Ordering,
+    ) {
+        let mut order = (0..entries.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| compare(&entries[*a], &entries[*b]));
+        if order.iter().enumerate().all(|(i, o)| i == *o) {
+            return;
+        }
+
+        let spans = entries.iter().map(|e| e.span()).collect::<Vec<_>>();
+        tokens.reorder(&spans, &order);
+    }
+
+    /// The decoded, possibly dotted, key
+    pub fn key(&self) -> &[String] {
+        &self.key
+    }
+
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    pub fn key_span(&self) -> Range<usize> {
+        self.key_span.clone()
+    }
+
+    pub fn value_span(&self) -> Range<usize> {
+        self.value_span.clone()
+    }
+}
+
+fn parse_key_value(
+    tokens: &TomlTokens<'_>,
+    key_start: usize,
+    end: usize,
+) -> (Vec<String>, Range<usize>, Range<usize>) {
+    let mut key = Vec::new();
+    let mut key_end = key_start;
+    let mut i = key_start;
+    while i < end {
+        let token = &tokens.tokens[i];
+        match token.kind {
+            TokenKind::SimpleKey => {
+                key.push(token.decoded.as_ref().unwrap_or(&token.raw).to_string());
+                key_end = i + 1;
+            }
+            TokenKind::KeySep | TokenKind::Whitespace => {}
+            _ => break,
+        }
+        i += 1;
+    }
+
+    // Skip `=` and surrounding whitespace
+    while i < end
+        && matches!(
+            tokens.tokens[i].kind,
+            TokenKind::KeyValSep | TokenKind::Whitespace
+        )
+    {
+        i += 1;
+    }
+    let value_start = i.min(end);
+    let value_end = value_end(tokens, value_start, end);
+
+    (key, key_start..key_end, value_start..value_end)
+}
+
+/// Index after the value starting at `start`
+pub(crate) fn value_end(tokens: &TomlTokens<'_>, start: usize, end: usize) -> usize {
+    let mut depth = 0_usize;
+    for i in start..end {
+        match tokens.tokens[i].kind {
+            TokenKind::ArrayOpen | TokenKind::InlineTableOpen => depth += 1,
+            TokenKind::ArrayClose | TokenKind::InlineTableClose => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            TokenKind::Scalar if depth == 0 => return i + 1,
+            _ => {}
+        }
+    }
+    end
+}
+
+/// Index after the newline ending the line that contains `i`
+fn line_end(tokens: &TomlTokens<'_>, i: usize, end: usize) -> usize {
+    (i..end)
+        .find(|&i| tokens.tokens[i].kind == TokenKind::Newline)
+        .map(|i| i + 1)
+        .unwrap_or(end)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::toml::Table;
+
+    fn keys(input: &str) -> Vec<Vec<String>> {
+        let tokens = TomlTokens::parse(input);
+        let tables = Table::new(&tokens);
+        let entries = Entry::parse(&tokens, tables[0].body_span());
+        Entry::runs(&entries)
+            .into_iter()
+            .map(|run| run.iter().map(|e| e.key().join(".")).collect())
+            .collect()
+    }
+
+    #[test]
+    fn empty_table() {
+        assert_eq!(keys("[a]\n"), Vec::<Vec<String>>::new());
+    }
+
+    #[test]
+    fn runs_split_on_blank_lines() {
+        assert_eq!(
+            keys(
+                "[a]
+b = 1
+a.c = { x = 1 }
+
+# dangling
+
+d = [
+  1,
+]
+# leading
+e = 2 # trailing
+[next]
+f = 1
+"
+            ),
+            vec![vec!["b", "a.c"], vec!["d", "e"]]
+        );
+    }
+
+    #[test]
+    fn inline_entries() {
+        let input = "a = { version = \"1\", b.c = { d = [1, 2] }, e = true }\n";
+        let tokens = TomlTokens::parse(input);
+        let entries = Entry::parse_inline(&tokens, 4);
+        let text = |span: Range<usize>| {
+            tokens.tokens[span]
+                .iter()
+                .map(|t| t.raw.as_ref())
+                .collect::<String>()
+        };
+        let actual = entries
+            .iter()
+            .map(|e| (e.key().join("."), text(e.span()), text(e.value_span())))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            vec![
+                (
+                    "version".to_owned(),
+                    "version = \"1\"".to_owned(),
+                    "\"1\"".to_owned()
+                ),
+                (
+                    "b.c".to_owned(),
+                    "b.c = { d = [1, 2] }".to_owned(),
+                    "{ d = [1, 2] }".to_owned()
+                ),
+                ("e".to_owned(), "e = true".to_owned(), "true".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn entry_spans() {
+        let input = "[a] # header
+# leading
+b = [1, 2] # trailing
+
+# next table
+[c]
+";
+        let tokens = TomlTokens::parse(input);
+        let tables = Table::new(&tokens);
+        let entries = Entry::parse(&tokens, tables[0].body_span());
+        assert_eq!(entries.len(), 1);
+        let text = |span: Range<usize>| {
+            tokens.tokens[span]
+                .iter()
+                .map(|t| t.raw.as_ref())
+                .collect::<String>()
+        };
+        assert_eq!(
+            text(entries[0].span()),
+            "# leading\nb = [1, 2] # trailing\n"
+        );
+        assert_eq!(text(entries[0].key_span()), "b");
+        assert_eq!(text(entries[0].value_span()), "[1, 2]");
+        assert_eq!(
+            text(tables[0].body_span()),
+            "# leading\nb = [1, 2] # trailing\n\n"
+        );
+    }
+}
@@ -1,6 +1,12 @@
+mod array;
+mod entry;
 mod table;
 mod tokens;
 
+pub use array::Array;
+pub use array::ArrayElement;
+pub use entry::Entry;
+pub use table::DottedTable;
 pub use table::Table;
 pub use tokens::Encoding;
 pub use tokens::ScalarKind;
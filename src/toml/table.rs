@@ -1,3 +1,4 @@
+use super::Entry;
 use super::TokenIndices;
 use super::TokenKind;
 use super::TomlTokens;
@@ -8,6 +9,10 @@ pub struct Table {
     start: usize,
     /// Equal to next table's header index or token count.
     end: usize,
+    /// From `[` through `]`
+    header: std::ops::Range<usize>,
+    /// From the line after the header up to the next table's `start` or token count.
+    body: std::ops::Range<usize>,
     is_array_table: bool,
 }
 
@@ -29,15 +34,18 @@ impl Table {
         // Second pass: construct tables with end boundaries
         let mut tables = Vec::new();
         for (idx, &(header_idx, start, is_array_table)) in header_info.iter().enumerate() {
-            let end = match header_info.get(idx + 1) {
-                Some(&(next_header_idx, _, _)) => next_header_idx,
-                None => tokens.len(),
+            let (end, body_end) = match header_info.get(idx + 1) {
+                Some(&(next_header_idx, next_start, _)) => (next_header_idx, next_start),
+                None => (tokens.len(), tokens.len()),
             };
-            let (name, _) = parse_table_name(tokens, header_idx + 1);
+            let (name, close_idx) = parse_table_name(tokens, header_idx + 1);
+            let body_start = find_line_end(tokens, close_idx).min(body_end);
             tables.push(Self {
                 name,
                 start,
                 end,
+                header: header_idx..close_idx + 1,
+                body: body_start..body_end,
                 is_array_table,
             });
         }
@@ -53,40 +61,143 @@ impl Table {
         self.start..self.end
     }
 
+    /// The table header, from `[` through `]`
+    pub fn header_span(&self) -> std::ops::Range<usize> {
+        self.header.clone()
+    }
+
+    /// Key/value pairs and comments after the header line.
+    ///
+    /// Unlike [`Table::span`], this excludes the next table's leading comments.
+    pub fn body_span(&self) -> std::ops::Range<usize> {
+        self.body.clone()
+    }
+
     pub fn is_array_table(&self) -> bool {
         self.is_array_table
     }
+
+    /// Rearranges `tables` so that position `i` holds `tables[order[i]]`
+    ///
+    /// Tables move along with their leading comments.
+    pub fn reorder(tokens: &mut TomlTokens<'_>, tables: &[Self], order: &[usize]) {
+        let spans = tables
+            .iter()
+            .enumerate()
+            .map(|(i, table)| {
+                let end = tables
+                    .get(i + 1)
+                    .map(|next| next.start)
+                    .unwrap_or(tokens.len());
+                table.start..end
+            })
+            .collect::<Vec<_>>();
+        tokens.reorder(&spans, order);
+    }
+}
+
+/// A table defined by dotted keys rather than a header, like `serde` in
+/// `serde.workspace = true`
+///
+/// Only the table named by the first part of a key is tracked, so `a.b.c = 1` defines `a`.
+pub struct DottedTable {
+    name: Vec<String>,
+    /// Index of the [`Table`] holding the keys, `None` for keys before the first header
+    parent: Option<usize>,
+    entries: Vec<Entry>,
+}
+
+impl DottedTable {
+    /// Collects the tables defined by dotted keys, in order of first appearance
+    #[tracing::instrument(skip_all)]
+    pub fn new(tokens: &TomlTokens<'_>, tables: &[Table]) -> Vec<Self> {
+        let root_end = tables.first().map(|t| t.start).unwrap_or(tokens.len());
+        let bodies = std::iter::once((None, 0..root_end)).chain(
+            tables
+                .iter()
+                .enumerate()
+                .map(|(i, t)| (Some(i), t.body_span())),
+        );
+
+        let mut dotted = Vec::<Self>::new();
+        for (parent, body) in bodies {
+            let first = dotted.len();
+            for entry in Entry::parse(tokens, body) {
+                let [key, _, ..] = entry.key() else {
+                    continue;
+                };
+                let existing = dotted[first..]
+                    .iter_mut()
+                    .find(|d| d.name.last() == Some(key));
+                match existing {
+                    Some(existing) => existing.entries.push(entry),
+                    None => {
+                        let mut name = parent
+                            .map(|p: usize| tables[p].name.clone())
+                            .unwrap_or_default();
+                        name.push(key.clone());
+                        dotted.push(Self {
+                            name,
+                            parent,
+                            entries: vec![entry],
+                        });
+                    }
+                }
+            }
+        }
+        dotted
+    }
+
+    /// The full name, including the parent table's
+    pub fn name(&self) -> &[String] {
+        &self.name
+    }
+
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    /// The entries with keys into this table, which may be apart from each other
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
 }
 
+/// Start of the comment lines directly above the header at `header_idx`, if any
 fn find_start(tokens: &TomlTokens<'_>, header_idx: usize) -> usize {
-    if header_idx == 0 {
-        return 0;
+    let mut start = header_idx;
+    // Each round takes one more comment line, until a blank or any other line
+    while let Some(comment) = comment_line_above(tokens, start) {
+        start = comment;
     }
+    start
+}
 
+/// The comment on the line above the one starting at `i`, when the line holds nothing else
+fn comment_line_above(tokens: &TomlTokens<'_>, i: usize) -> Option<usize> {
+    let mut indices = TokenIndices::from_index(i);
     let mut newline_count = 0;
-    let mut indices = TokenIndices::from_index(header_idx);
+    let mut comment = None;
 
     while let Some(i) = indices.prev_index(tokens) {
         match tokens.tokens[i].kind {
-            TokenKind::Comment => {
-                // Adjacent comment is a leading comment
-                if newline_count == 1 {
-                    return i;
-                }
-                return header_idx;
-            }
-            TokenKind::Newline => {
-                newline_count += 1;
-                if newline_count > 1 {
-                    return header_idx;
-                }
-            }
+            TokenKind::Comment if newline_count == 1 && comment.is_none() => comment = Some(i),
+            TokenKind::Newline if newline_count == 0 => newline_count += 1,
+            TokenKind::Newline => return comment,
             TokenKind::Whitespace => {}
-            _ => return header_idx,
+            _ => return None,
         }
     }
 
-    header_idx
+    comment
+}
+
+/// Index after the newline ending the line that contains `i`
+fn find_line_end(tokens: &TomlTokens<'_>, i: usize) -> usize {
+    (i..tokens.len())
+        .find(|&i| tokens.tokens[i].kind == TokenKind::Newline)
+        .map(|i| i + 1)
+        .unwrap_or(tokens.len())
 }
 
 fn parse_table_name(tokens: &TomlTokens<'_>, start: usize) -> (Vec<String>, usize) {
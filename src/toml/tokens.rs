@@ -58,6 +58,15 @@ impl<'i> TomlTokens<'i> {
         }
     }
 
+    /// Tokenizes `input` for splicing into another document
+    pub fn parse_snippet(input: &str) -> Vec<TomlToken<'static>> {
+        TomlTokens::parse(input)
+            .tokens
+            .into_iter()
+            .map(TomlToken::into_owned)
+            .collect()
+    }
+
     pub fn indices(&self) -> impl Iterator<Item = usize> + use<> {
         0..self.tokens.len()
     }
@@ -75,6 +84,38 @@ impl<'i> TomlTokens<'i> {
             .retain(|t| !(matches!(t.kind, TokenKind::Whitespace) && t.raw.is_empty()));
     }
 
+    /// Rearranges the ordered `spans` so that position `i` holds `spans[order[i]]`
+    ///
+    /// Trailing newlines stay in their position, so a span lacking one (like at
+    /// the end of the document) can be moved anywhere.  So do the tokens between
+    /// spans, like blank lines.
+    pub fn reorder(&mut self, spans: &[std::ops::Range<usize>], order: &[usize]) {
+        let (Some(first), Some(last)) = (spans.first(), spans.last()) else {
+            return;
+        };
+        let content_end = |span: &std::ops::Range<usize>| {
+            let mut end = span.end;
+            while span.start < end
+                && matches!(
+                    self.tokens[end - 1].kind,
+                    TokenKind::Newline | TokenKind::Whitespace
+                )
+            {
+                end -= 1;
+            }
+            end
+        };
+
+        let mut reordered = Vec::with_capacity(last.end - first.start);
+        for (i, (slot, &from)) in spans.iter().zip(order).enumerate() {
+            let from = &spans[from];
+            let gap_end = spans.get(i + 1).map(|next| next.start).unwrap_or(slot.end);
+            reordered.extend(self.tokens[from.start..content_end(from)].iter().cloned());
+            reordered.extend(self.tokens[content_end(slot)..gap_end].iter().cloned());
+        }
+        self.tokens.splice(first.start..last.end, reordered);
+    }
+
     #[allow(clippy::inherent_to_string_shadow_display, reason = "optimized")]
     pub fn to_string(&self) -> String {
         use std::fmt::Write as _;
@@ -94,7 +135,7 @@ impl std::fmt::Display for TomlTokens<'_> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct TomlToken<'i> {
     pub kind: TokenKind,
     pub encoding: Option<Encoding>,
@@ -104,6 +145,51 @@ pub struct TomlToken<'i> {
 }
 
 impl TomlToken<'_> {
+    pub fn into_owned(self) -> TomlToken<'static> {
+        TomlToken {
+            kind: self.kind,
+            encoding: self.encoding,
+            decoded: self.decoded.map(|d| Cow::Owned(d.into_owned())),
+            scalar: self.scalar,
+            raw: Cow::Owned(self.raw.into_owned()),
+        }
+    }
+
+    /// Replaces a string scalar's value, keeping its quoting when possible
+    pub fn set_string(&mut self, value: &str) {
+        use toml_writer::TomlWrite as _;
+
+        let quote = match self.encoding {
+            Some(Encoding::BasicString)
+                if !value.contains(['"', '\\']) && !value.contains(char::is_control) =>
+            {
+                Some('"')
+            }
+            Some(Encoding::LiteralString)
+                if !value.contains('\'') && !value.contains(char::is_control) =>
+            {
+                Some('\'')
+            }
+            _ => None,
+        };
+        let raw = match quote {
+            Some(quote) => format!("{quote}{value}{quote}"),
+            None => {
+                let mut raw = String::new();
+                raw.value(toml_writer::TomlStringBuilder::new(value).as_default())
+                    .unwrap();
+                self.encoding = raw.chars().next().and_then(|q| match q {
+                    '"' => Some(Encoding::BasicString),
+                    '\'' => Some(Encoding::LiteralString),
+                    _ => None,
+                });
+                raw
+            }
+        };
+        self.raw = Cow::Owned(raw);
+        self.decoded = Some(Cow::Owned(value.to_owned()));
+    }
+
     pub const EMPTY: Self = Self {
         kind: TokenKind::Whitespace,
         encoding: None,
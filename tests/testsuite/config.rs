@@ -0,0 +1,83 @@
+use std::path::Path;
+
+use cargo_cargofmt::config::ConfigOverrides;
+use cargo_cargofmt::config::load_config_with_overrides;
+
+/// Writes `config` to `rustfmt.toml` in a fresh directory, returning the directory
+#[track_caller]
+fn workspace(name: &str, config: &str) -> std::path::PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("rustfmt.toml"), config).unwrap();
+    dir
+}
+
+/// `path` relative to the current directory, like a user would pass it
+#[track_caller]
+fn relative(path: &Path) -> &Path {
+    path.strip_prefix(std::env::current_dir().unwrap())
+        .expect("target directory is within the package")
+}
+
+#[test]
+fn path_overrides_with_relative_config_path() {
+    let dir = workspace(
+        "path_overrides_with_relative_config_path",
+        r#"
+[[overrides]]
+paths = ["examples/*"]
+max_width = 80
+"#,
+    );
+    let overrides = ConfigOverrides {
+        config_path: Some(relative(&dir).join("rustfmt.toml")),
+        ..Default::default()
+    };
+
+    let config = load_config_with_overrides(
+        &dir.join("examples/wide/Cargo.toml"),
+        &overrides,
+        &mut Vec::new(),
+    )
+    .unwrap();
+    assert_eq!(config.max_width, 80);
+    let config =
+        load_config_with_overrides(&dir.join("Cargo.toml"), &overrides, &mut Vec::new()).unwrap();
+    assert_eq!(config.max_width, 100);
+}
+
+#[test]
+fn unknown_keys_are_reported_alike() {
+    let dir = workspace(
+        "unknown_keys_are_reported_alike",
+        r#"
+max_widht = 80
+imports_granularity = "Crate"
+"#,
+    );
+    let overrides = ConfigOverrides {
+        values: vec![("tab_spacse".to_owned(), "2".to_owned())],
+        ..Default::default()
+    };
+
+    let mut warnings = Vec::new();
+    let config =
+        load_config_with_overrides(&dir.join("Cargo.toml"), &overrides, &mut warnings).unwrap();
+    assert_eq!(config.max_width, 100);
+    let config_path = dir.join("rustfmt.toml");
+    assert_eq!(
+        warnings,
+        [
+            format!(
+                "unknown configuration option `max_widht` in `{}`",
+                config_path.display()
+            ),
+            format!(
+                "unknown configuration option `imports_granularity` in `{}`",
+                config_path.display()
+            ),
+            "unknown configuration option `tab_spacse` in `--config tab_spacse=2`".to_owned(),
+        ]
+    );
+}
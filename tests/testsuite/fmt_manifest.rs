@@ -0,0 +1,630 @@
+use cargo_cargofmt::config::Config;
+use snapbox::IntoData;
+use snapbox::assert_data_eq;
+use snapbox::str;
+
+#[track_caller]
+fn fmt(input: &str, config: &str, expected: impl IntoData) {
+    let config: Config = toml::de::from_str(config).unwrap();
+    let actual = cargo_cargofmt::fmt_manifest(input, config)
+        .unwrap()
+        .unwrap();
+    assert_data_eq!(actual, expected);
+}
+
+#[track_caller]
+fn fmt_with_warnings(
+    input: &str,
+    config: &str,
+    expected: impl IntoData,
+    expected_warnings: impl IntoData,
+) {
+    let config: Config = toml::de::from_str(config).unwrap();
+    let mut warnings = Vec::new();
+    let actual = cargo_cargofmt::fmt_manifest_with_warnings(
+        input,
+        None,
+        config,
+        &mut warnings,
+        &mut Vec::new(),
+    )
+    .unwrap()
+    .unwrap();
+    assert_data_eq!(actual, expected);
+    assert_data_eq!(warnings.join("\n"), expected_warnings);
+}
+
+/// Formats `input` like [`fmt`], then checks formatting the result changes nothing
+#[track_caller]
+fn fmt_twice(input: &str, config: &str, expected: impl IntoData) {
+    let parse = || toml::de::from_str::<Config>(config).unwrap();
+    let actual = cargo_cargofmt::fmt_manifest(input, parse())
+        .unwrap()
+        .unwrap();
+    assert_data_eq!(&actual, expected);
+    let again = cargo_cargofmt::fmt_manifest(&actual, parse())
+        .unwrap()
+        .unwrap();
+    assert_eq!(actual, again, "formatting again changed the output");
+}
+
+const INPUT: &str = r#"[package]
+name = "foo"
+released = 2025-12-26 10:30:00
+keywords = ["a-very-long-keyword", "another-very-long-keyword", "and-one-more"]
+
+[dependencies]
+[dependencies.serde]
+version = "1"
+"#;
+
+#[test]
+fn style_edition_2021() {
+    fmt(
+        INPUT,
+        r#"style_edition = "2021""#,
+        str![[r#"
+[package]
+name = "foo"
+released = 2025-12-26 10:30:00
+keywords = ["a-very-long-keyword", "another-very-long-keyword", "and-one-more"]
+
+[dependencies]
+[dependencies.serde]
+version = "1"
+
+"#]],
+    );
+}
+
+#[test]
+fn style_edition_2024() {
+    fmt(
+        INPUT,
+        r#"style_edition = "2024""#,
+        str![[r#"
+[package]
+name = "foo"
+released = 2025-12-26T10:30:00
+keywords = [
+    "a-very-long-keyword",
+    "another-very-long-keyword",
+    "and-one-more",
+]
+
+[dependencies.serde]
+version = "1"
+
+"#]],
+    );
+}
+
+#[test]
+fn style_edition_default() {
+    fmt(
+        INPUT,
+        "",
+        str![[r#"
+[package]
+name = "foo"
+released = 2025-12-26T10:30:00
+keywords = [
+    "a-very-long-keyword",
+    "another-very-long-keyword",
+    "and-one-more",
+]
+
+[dependencies.serde]
+version = "1"
+
+"#]],
+    );
+}
+
+#[test]
+fn pass_switches_override_style_edition() {
+    fmt(
+        r#"[package]
+name = 'foo'
+released = 2025-12-26 10:30:00
+keywords = ["a-very-long-keyword", "another-very-long-keyword", "and-one-more"]
+
+[dependencies]
+[dependencies.serde]
+version = "1"
+"#,
+        r#"
+style_edition = "2021"
+reflow_arrays = true
+normalize_strings = false
+"#,
+        str![[r#"
+[package]
+name = 'foo'
+released = 2025-12-26 10:30:00
+keywords = [
+    "a-very-long-keyword",
+    "another-very-long-keyword",
+    "and-one-more",
+]
+
+[dependencies]
+[dependencies.serde]
+version = "1"
+
+"#]],
+    );
+}
+
+#[test]
+fn normalized_features_are_reflowed() {
+    fmt(
+        r#"[features]
+serde = ["dep:serde", "std"]
+default = ["std", "serde", "dep:once_cell", "std", "alloc", "serde?/alloc"]
+std = []
+"#,
+        r#"
+normalize_features = true
+array_width = 40
+"#,
+        str![[r#"
+[features]
+default = [
+    "alloc",
+    "serde",
+    "std",
+    "dep:once_cell",
+    "serde?/alloc",
+]
+serde = ["std", "dep:serde"]
+std = []
+
+"#]],
+    );
+}
+
+#[test]
+fn warnings_are_collected() {
+    let config: Config = toml::de::from_str("rewrite_deprecated_keys = true").unwrap();
+    let mut warnings = Vec::new();
+    let actual = cargo_cargofmt::fmt_manifest_with_warnings(
+        r#"[dev-dependencies]
+[dev_dependencies]
+"#,
+        None,
+        config,
+        &mut warnings,
+        &mut Vec::new(),
+    )
+    .unwrap()
+    .unwrap();
+    assert_data_eq!(
+        actual,
+        str![[r#"
+[dev-dependencies]
+[dev_dependencies]
+
+"#]]
+    );
+    assert_data_eq!(
+        warnings.join("\n"),
+        str![
+            "both `dev_dependencies` and `dev-dependencies` are present, leaving `dev_dependencies` as is"
+        ]
+    );
+}
+
+#[test]
+fn dotted_keys_outside_dependencies() {
+    fmt(
+        r#"package.name = "foo"
+package.license = "MIT/Apache-2.0"
+
+[dependencies]
+serde.version = "1"
+"#,
+        r#"
+dotted_keys = "StandardTables"
+normalize_licenses = true
+"#,
+        str![[r#"
+package.name = "foo"
+package.license = "MIT OR Apache-2.0"
+
+[dependencies.serde]
+version = "1"
+
+"#]],
+    );
+}
+
+#[test]
+fn converted_dependency_tables_keep_comments() {
+    fmt(
+        r#"[dependencies]
+# about serde
+[dependencies.serde] # hdr
+version = "1"
+"#,
+        "convert_dependency_tables = true",
+        str![[r#"
+[dependencies]
+# about serde
+serde = { version = "1" } # hdr
+
+"#]],
+    );
+}
+
+#[test]
+fn skipped_tables_not_inlined() {
+    fmt_with_warnings(
+        r#"[dependencies]
+anyhow = '1'
+[dependencies.serde]
+version = '1'
+"#,
+        r#"
+skip_tables = ["dependencies"]
+convert_dependency_tables = true
+"#,
+        str![[r#"
+[dependencies]
+anyhow = '1'
+[dependencies.serde]
+version = '1'
+
+"#]],
+        str![],
+    );
+}
+
+#[test]
+fn skipped_tables_not_expanded() {
+    fmt_with_warnings(
+        r#"[dependencies]
+serde = { version = '1', features = ['derive', 'rc'], default-features = false }
+"#,
+        r#"
+skip_tables = ["dependencies"]
+convert_dependency_tables = true
+inline_table_width = 20
+"#,
+        str![[r#"
+[dependencies]
+serde = { version = '1', features = ['derive', 'rc'], default-features = false }
+
+"#]],
+        str![],
+    );
+}
+
+#[test]
+fn skipped_tables_with_dotted_keys() {
+    fmt_with_warnings(
+        r#"[dependencies]
+serde.version = '1'
+"#,
+        r#"
+skip_tables = ["dependencies"]
+dotted_keys = "StandardTables"
+"#,
+        str![[r#"
+[dependencies]
+serde.version = '1'
+
+"#]],
+        str![],
+    );
+}
+
+#[test]
+fn skipped_tables_in_dotted_keys() {
+    fmt_with_warnings(
+        r#"[package]
+name = 'foo'
+metadata.release.foo = [ "a" , "b" ]
+"#,
+        r#"
+skip_tables = ["package.metadata.release"]
+dotted_keys = "StandardTables"
+"#,
+        str![[r#"
+[package]
+name = "foo"
+metadata.release.foo = [ "a" , "b" ]
+
+"#]],
+        str![],
+    );
+}
+
+#[test]
+fn skipped_tables_reordered() {
+    fmt_with_warnings(
+        r#"[dependencies]
+serde = '1'
+
+[package]
+name = 'foo'
+
+[dependencies.toml]
+version = '1'
+"#,
+        r#"
+skip_tables = ["dependencies"]
+reorder_tables = true
+group_subtables = true
+"#,
+        str![[r#"
+[package]
+name = "foo"
+
+[dependencies]
+serde = '1'
+
+[dependencies.toml]
+version = '1'
+
+"#]],
+        str![],
+    );
+}
+
+#[test]
+fn skipped_tables_kept_when_redundant() {
+    fmt_with_warnings(
+        r#"[package]
+name = 'foo'
+
+[lib]
+path = 'src/lib.rs'
+"#,
+        r#"
+skip_tables = ["lib"]
+remove_redundant_defaults = true
+"#,
+        str![[r#"
+[package]
+name = "foo"
+
+[lib]
+path = 'src/lib.rs'
+
+"#]],
+        str![],
+    );
+}
+
+#[test]
+fn no_trailing_newline_style_edition_2021() {
+    fmt(
+        "a = [\n    1,\n]",
+        r#"style_edition = "2021""#,
+        str![[r#"
+a = [
+    1,
+]
+"#]],
+    );
+}
+
+#[test]
+fn no_trailing_newline_without_reflow_arrays() {
+    fmt(
+        "a = [\n    1,\n]",
+        "reflow_arrays = false",
+        str![[r#"
+a = [
+    1,
+]
+"#]],
+    );
+}
+
+#[test]
+fn dotted_keys_keep_comments() {
+    fmt(
+        r#"[dependencies]
+# about serde
+serde.version = "1" # pinned
+# for derives
+serde.features = ["derive"]
+"#,
+        r#"dotted_keys = "StandardTables""#,
+        str![[r#"
+[dependencies.serde]
+# about serde
+version = "1" # pinned
+# for derives
+features = ["derive"]
+
+"#]],
+    );
+}
+
+#[test]
+fn skipped_tables_keep_their_own_comments() {
+    fmt(
+        r#"[package]
+name = 'foo'
+
+# comment for lib
+[lib]
+path = 'lib.rs'
+
+# comment for dependencies
+[dependencies]
+serde = '1'
+
+# comment for features
+[features]
+default = []
+"#,
+        r#"
+skip_tables = ["lib"]
+reorder_tables = true
+"#,
+        str![[r#"
+[package]
+name = "foo"
+
+# comment for lib
+[lib]
+path = 'lib.rs'
+
+# comment for features
+[features]
+default = []
+
+# comment for dependencies
+[dependencies]
+serde = "1"
+
+"#]],
+    );
+}
+
+#[test]
+fn expanded_dependencies_are_stable() {
+    fmt_twice(
+        r#"[dependencies]
+serde = { version = "1", features = ["derive", "rc"], default-features = false }
+"#,
+        r#"
+style_edition = "2024"
+convert_dependency_tables = true
+inline_table_width = 20
+"#,
+        str![[r#"
+[dependencies.serde]
+version = "1"
+features = ["derive", "rc"]
+default-features = false
+
+"#]],
+    );
+}
+
+#[test]
+fn inlined_dependencies_are_stable() {
+    fmt_twice(
+        r#"[dependencies.anyhow]
+# keeps this a table
+version = "1"
+
+[dependencies.serde]
+version = "1"
+"#,
+        r#"
+style_edition = "2024"
+group_subtables = true
+convert_dependency_tables = true
+"#,
+        str![[r#"
+[dependencies]
+serde = { version = "1" }
+
+[dependencies.anyhow]
+# keeps this a table
+version = "1"
+
+"#]],
+    );
+}
+
+const WORKSPACE: &str = r#"
+[workspace.package]
+edition = "2021"
+
+[workspace.dependencies]
+serde = "1"
+"#;
+
+#[track_caller]
+fn fmt_with_changes(
+    input: &str,
+    config: &str,
+    expected: impl IntoData,
+    expected_changes: impl IntoData,
+) {
+    let config: Config = toml::de::from_str(config).unwrap();
+    let mut changes = Vec::new();
+    let actual = cargo_cargofmt::fmt_manifest_with_warnings(
+        input,
+        Some(WORKSPACE),
+        config,
+        &mut Vec::new(),
+        &mut changes,
+    )
+    .unwrap();
+    assert_data_eq!(actual.unwrap_or_default(), expected);
+    assert_data_eq!(changes.join("\n"), expected_changes);
+}
+
+#[test]
+fn inherited_fields_are_formatted() {
+    fmt_with_changes(
+        r#"[package]
+edition = '2021'
+
+[dependencies]
+serde = '1'
+"#,
+        r#"
+inherit_workspace_fields = true
+normalize_strings = true
+"#,
+        str![[r#"
+[package]
+edition.workspace = true
+
+[dependencies]
+serde = { workspace = true }
+
+"#]],
+        str![[r#"
+inheriting `package.edition` from `workspace.package.edition`
+inheriting `dependencies.serde` from `workspace.dependencies.serde`
+"#]],
+    );
+}
+
+#[test]
+fn skipped_tables_are_not_inherited() {
+    fmt_with_changes(
+        r#"[package]
+edition = "2021"
+
+[dependencies]
+serde = "1"
+"#,
+        r#"
+inherit_workspace_fields = true
+skip_tables = ["dependencies"]
+"#,
+        str![[r#"
+[package]
+edition.workspace = true
+
+[dependencies]
+serde = "1"
+
+"#]],
+        str!["inheriting `package.edition` from `workspace.package.edition`"],
+    );
+}
+
+#[test]
+fn generated_files_are_not_inherited() {
+    fmt_with_changes(
+        r#"# @generated
+[package]
+edition = "2021"
+"#,
+        "inherit_workspace_fields = true",
+        str![],
+        str![],
+    );
+}
@@ -1 +1,2 @@
-
+mod config;
+mod fmt_manifest;